@@ -37,6 +37,7 @@ async fn main() -> anyhow::Result<()> {
             api_key: None,
             model_id: None,
             description: None,
+            context_window: None,
         });
         config.default_model = "cli-override".to_string();
     } else if args.mock {
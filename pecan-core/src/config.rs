@@ -4,6 +4,7 @@ use std::fs;
 use std::path::{PathBuf};
 use anyhow::Result;
 use directories::ProjectDirs;
+use pecan_providers::ProviderConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelDef {
@@ -13,6 +14,10 @@ pub struct ModelDef {
     pub api_key: Option<String>,
     pub model_id: Option<String>,
     pub description: Option<String>,
+    /// Maximum context length (in tokens) advertised by this model, used to
+    /// render the status-bar budget meter. `None` falls back to a default.
+    #[serde(default)]
+    pub context_window: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +25,19 @@ pub struct ToolConfig {
     pub require_approval: bool,
     pub allowed_shell_commands: Vec<String>,
     pub blocked_shell_commands: Vec<String>,
+    /// Maximum number of tool calls to execute concurrently within a single turn.
+    /// When `None`, the pool is sized from the number of available CPUs.
+    #[serde(default)]
+    pub max_parallel_tools: Option<usize>,
+    /// Ceiling on the number of model round trips `Agent::chat_internal` will
+    /// take in a single turn before giving up, so a model that keeps emitting
+    /// tool calls can't loop forever.
+    #[serde(default = "default_max_tool_steps")]
+    pub max_tool_steps: usize,
+}
+
+fn default_max_tool_steps() -> usize {
+    25
 }
 
 impl Default for ToolConfig {
@@ -28,16 +46,109 @@ impl Default for ToolConfig {
             require_approval: true,
             allowed_shell_commands: vec!["ls".to_string(), "cat".to_string(), "grep".to_string(), "pwd".to_string()],
             blocked_shell_commands: vec!["rm".to_string(), "mv".to_string()],
+            max_parallel_tools: None,
+            max_tool_steps: default_max_tool_steps(),
+        }
+    }
+}
+
+impl ToolConfig {
+    /// Resolves the effective parallel-tool pool size, falling back to the CPU
+    /// count (and then to 1) when `max_parallel_tools` is unset.
+    pub fn parallel_pool_size(&self) -> usize {
+        self.max_parallel_tools
+            .filter(|n| *n > 0)
+            .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Target ceiling, in estimated tokens, for the live conversation history.
+    /// Once exceeded, the oldest turns are folded into a single summary.
+    pub context_token_budget: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            context_token_budget: 8192,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Master switch; when `false` nothing is emitted regardless of the flags below.
+    pub enabled: bool,
+    /// Ring the terminal bell (`\x07`) alongside any desktop notification.
+    pub bell: bool,
+    /// Post an OS desktop notification via `notify-rust`.
+    pub desktop: bool,
+    /// Notify when a backgrounded autonomous loop finishes or fails.
+    pub on_task_complete: bool,
+    /// Notify when a tool call is waiting on the user's approval.
+    pub on_approval: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bell: true,
+            desktop: false,
+            on_task_complete: true,
+            on_approval: true,
+        }
+    }
+}
+
+/// Default action → key-chord map, shipping the TUI's historical bindings so an
+/// absent `keymaps` section leaves behaviour unchanged.
+pub fn default_keymaps() -> HashMap<String, String> {
+    [
+        ("quit", "ctrl+c"),
+        ("complete", "tab"),
+        ("submit", "enter"),
+        ("newline", "shift+enter"),
+        ("pause_toggle", "ctrl+p"),
+        ("next_buffer", "ctrl+tab"),
+        ("prev_buffer", "ctrl+shift+tab"),
+        ("scroll_up", "pageup"),
+        ("scroll_down", "pagedown"),
+        ("theme_picker", "ctrl+t"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub default_model: String,
     pub models: HashMap<String, ModelDef>,
+    /// Named provider configs, keyed by the name a `CreateSessionRequest` or
+    /// `Agent::switch_model` caller selects by. Checked before falling back
+    /// to the legacy `models` table, so existing configs keep working as-is.
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderConfig>,
     #[serde(default)]
     pub tools: ToolConfig,
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default = "default_keymaps")]
+    pub keymaps: HashMap<String, String>,
+    /// Name of the last theme selected via `/theme` or the theme picker,
+    /// resolved against the TUI's `ThemeRegistry` at startup. `"auto"`
+    /// selects light or dark based on the terminal's real background color.
+    #[serde(default = "default_theme")]
+    pub theme: String,
+}
+
+fn default_theme() -> String {
+    "dracula".to_string()
 }
 
 impl Default for Config {
@@ -50,12 +161,18 @@ impl Default for Config {
             api_key: None,
             model_id: None,
             description: Some("Mock model for testing".to_string()),
+            context_window: None,
         });
         
         Self {
             default_model: "mock".to_string(),
             models,
+            providers: HashMap::new(),
             tools: ToolConfig::default(),
+            memory: MemoryConfig::default(),
+            notifications: NotificationConfig::default(),
+            keymaps: default_keymaps(),
+            theme: default_theme(),
         }
     }
 }
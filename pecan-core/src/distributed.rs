@@ -0,0 +1,361 @@
+//! Optional distributed mode: a coordinator hands queued `Task`s to remote
+//! worker agents instead of running every step in-process. Workers run a plain
+//! [`Agent`] in a receive loop; the coordinator tracks live workers, reserves a
+//! per-task artifact directory, and folds incoming [`TaskUpdate`]s back into the
+//! shared [`TaskStack`]. `run_task`'s [`ArtifactCollector`] saves each tool's
+//! output plus any file `write_file` wrote into that reserved directory for
+//! the duration of the task, so `TaskUpdate.artifacts` reports back exactly
+//! what the task produced.
+//!
+//! In-process workers talk to the coordinator over a plain `tokio::mpsc`
+//! `WorkerChannel`. Remote workers use the same `WorkerChannel` on the
+//! coordinator side, bridged to a TCP connection by `Coordinator::accept_workers`
+//! / `bridge_worker_socket`; the worker process itself calls `run_remote_worker`.
+//! Both ends of the socket speak the newline-delimited JSON frames from
+//! `crate::protocol`, so a `RequestedTask`/`TaskUpdate` looks identical to
+//! either side regardless of transport.
+
+use crate::protocol::{read_frame, write_frame, RequestedTask, TaskState, TaskUpdate};
+use crate::{Agent, AgentEvent, TaskStack, TaskStatus};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use tokio::io::{split, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+/// A coordinator-side handle to a connected worker.
+pub struct WorkerHandle {
+    pub id: Uuid,
+    requests: mpsc::Sender<RequestedTask>,
+}
+
+/// The worker-side endpoint handed out by [`Coordinator::register_worker`]: the
+/// stream of requested tasks plus the channel to report updates on. It holds a
+/// strong reference to the [`WorkerHandle`] so that dropping the channel (when
+/// the worker loop exits) lets the coordinator's weak reference lapse and the
+/// worker be reaped.
+pub struct WorkerChannel {
+    pub id: Uuid,
+    pub requests: mpsc::Receiver<RequestedTask>,
+    pub updates: mpsc::Sender<TaskUpdate>,
+    _keepalive: Arc<WorkerHandle>,
+}
+
+/// Dispatches tasks to workers and applies their updates to the task stack.
+pub struct Coordinator {
+    workers: HashMap<Uuid, Weak<WorkerHandle>>,
+    /// The coordinator's own spare sender, dropped by `run` so the update loop
+    /// terminates once every worker has disconnected.
+    updates_tx: Option<mpsc::Sender<TaskUpdate>>,
+    updates_rx: mpsc::Receiver<TaskUpdate>,
+    task_stack: Arc<Mutex<TaskStack>>,
+    artifacts_root: PathBuf,
+}
+
+impl Coordinator {
+    pub fn new(task_stack: Arc<Mutex<TaskStack>>, artifacts_root: impl Into<PathBuf>) -> Self {
+        let (updates_tx, updates_rx) = mpsc::channel(64);
+        Self {
+            workers: HashMap::new(),
+            updates_tx: Some(updates_tx),
+            updates_rx,
+            task_stack,
+            artifacts_root: artifacts_root.into(),
+        }
+    }
+
+    /// Registers a new worker, returning the channel the worker loop consumes.
+    pub fn register_worker(&mut self) -> WorkerChannel {
+        let id = Uuid::new_v4();
+        let (requests_tx, requests_rx) = mpsc::channel(16);
+        let handle = Arc::new(WorkerHandle { id, requests: requests_tx });
+        self.workers.insert(id, Arc::downgrade(&handle));
+        let updates = self
+            .updates_tx
+            .clone()
+            .expect("register_worker called after run() dropped the update sender");
+        WorkerChannel {
+            id,
+            requests: requests_rx,
+            updates,
+            _keepalive: handle,
+        }
+    }
+
+    /// Reserves `artifacts/<task_id>/`, tolerating a pre-existing directory.
+    pub fn reserve_artifact_dir(&self, task_id: Uuid) -> Result<PathBuf> {
+        let dir = self.artifacts_root.join(task_id.to_string());
+        match std::fs::create_dir_all(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(dir)
+    }
+
+    /// Hands a task to the first live worker, reaping any dead workers on the way.
+    /// The reserved `artifacts/<id>/` directory is stamped onto the task so the
+    /// worker knows where to save what it produces.
+    pub async fn dispatch(&mut self, mut task: RequestedTask) -> Result<Uuid> {
+        task.artifact_dir = Some(self.reserve_artifact_dir(task.id)?);
+        self.reap_dead_workers();
+
+        let worker = self
+            .workers
+            .values()
+            .filter_map(Weak::upgrade)
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No live workers available"))?;
+
+        worker
+            .requests
+            .send(task)
+            .await
+            .map_err(|_| anyhow::anyhow!("Worker {} disconnected during dispatch", worker.id))?;
+        Ok(worker.id)
+    }
+
+    fn reap_dead_workers(&mut self) {
+        self.workers.retain(|_, w| w.strong_count() > 0);
+    }
+
+    /// Accepts worker connections on `listener` until it errors, bridging
+    /// each one to a freshly `register_worker`-ed `WorkerChannel` over the
+    /// framed-JSON wire protocol so remote worker processes can be dispatched
+    /// to exactly like an in-process one.
+    pub async fn accept_workers(coordinator: Arc<Mutex<Coordinator>>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let channel = coordinator.lock().await.register_worker();
+            tracing::info!("Worker {} connected from {}", channel.id, peer);
+            tokio::spawn(async move {
+                if let Err(e) = bridge_worker_socket(channel, stream).await {
+                    tracing::warn!("Worker bridge failed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Consumes worker updates, syncing the task stack until all senders drop.
+    pub async fn run(&mut self) -> Result<()> {
+        // Drop our own spare sender so the loop terminates once every worker
+        // (and its update sender) has gone away.
+        self.updates_tx.take();
+
+        while let Some(update) = self.updates_rx.recv().await {
+            self.apply_update(update).await;
+        }
+        Ok(())
+    }
+
+    async fn apply_update(&self, update: TaskUpdate) {
+        tracing::info!("TaskUpdate for {}: {:?}", update.id, update.state);
+        let mut stack = self.task_stack.lock().await;
+        match update.state {
+            TaskState::Running => stack.update_status(update.id, TaskStatus::InProgress),
+            TaskState::Completed => stack.complete(update.id),
+            TaskState::Failed => stack.update_status(
+                update.id,
+                TaskStatus::Failed(update.output.unwrap_or_else(|| "worker failed".to_string())),
+            ),
+        }
+    }
+}
+
+/// A worker process: a receive loop that pulls tasks, runs `chat()`, and reports.
+pub struct Worker {
+    pub agent: Arc<Agent>,
+}
+
+impl Worker {
+    pub fn new(agent: Arc<Agent>) -> Self {
+        Self { agent }
+    }
+
+    /// Pulls requested tasks until the coordinator closes the channel.
+    pub async fn run(self, mut channel: WorkerChannel) -> Result<()> {
+        while let Some(task) = channel.requests.recv().await {
+            let _ = channel
+                .updates
+                .send(TaskUpdate {
+                    id: task.id,
+                    state: TaskState::Running,
+                    output: None,
+                    artifacts: Vec::new(),
+                })
+                .await;
+
+            let update = run_task(&self.agent, &task).await;
+            let _ = channel.updates.send(update).await;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `task` through `agent.chat()` and turns the outcome into a
+/// `TaskUpdate`. If the coordinator reserved an `artifact_dir` for this task,
+/// an `ArtifactCollector` also saves every tool's output and any file
+/// `write_file` touched into that directory for the `artifacts` list. Shared
+/// by the in-process `Worker` and `run_remote_worker` so both transports
+/// report artifacts identically.
+async fn run_task(agent: &Agent, task: &RequestedTask) -> TaskUpdate {
+    let prompt = match &task.context {
+        Some(context) => format!("{}\n\n{}", context, task.description),
+        None => task.description.clone(),
+    };
+
+    let (status, artifacts) = match &task.artifact_dir {
+        Some(dir) => {
+            let collector = ArtifactCollector::attach(agent, dir.clone()).await;
+            let status = agent.chat(prompt).await;
+            (status, collector.finish(agent).await)
+        }
+        None => (agent.chat(prompt).await, Vec::new()),
+    };
+
+    match status {
+        Ok(status) => TaskUpdate {
+            id: task.id,
+            state: TaskState::Completed,
+            output: Some(status.to_string()),
+            artifacts,
+        },
+        Err(e) => TaskUpdate {
+            id: task.id,
+            state: TaskState::Failed,
+            output: Some(e.to_string()),
+            artifacts,
+        },
+    }
+}
+
+/// Subscribes to one `chat()` turn's `AgentEvent`s and saves what they
+/// produce into a reserved artifact directory: every tool's output as
+/// `NN-<tool>.txt`, and a copy of any file a `write_file` call wrote (by
+/// filename only, so a tool argument can't escape the reserved directory via
+/// `..`). `Agent::set_event_sender` only holds one sink at a time, so this is
+/// only safe while nothing else is watching the same agent's events
+/// concurrently - true here since a `Worker` drives one task at a time.
+struct ArtifactCollector {
+    handle: tokio::task::JoinHandle<Vec<PathBuf>>,
+}
+
+impl ArtifactCollector {
+    async fn attach(agent: &Agent, dir: PathBuf) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        agent.set_event_sender(Some(tx)).await;
+
+        let handle = tokio::spawn(async move {
+            let _ = fs::create_dir_all(&dir);
+            let mut artifacts = Vec::new();
+            let mut written_paths: Vec<String> = Vec::new();
+            let mut step = 0usize;
+
+            while let Some(event) = rx.recv().await {
+                match event {
+                    AgentEvent::ToolStarted { name, arguments } if name == "write_file" => {
+                        if let Some(path) = arguments.get("path").and_then(|v| v.as_str()) {
+                            written_paths.push(path.to_string());
+                        }
+                    }
+                    AgentEvent::ToolFinished { name, output } => {
+                        step += 1;
+                        let dest = dir.join(format!("{:02}-{}.txt", step, name));
+                        if fs::write(&dest, &output).is_ok() {
+                            artifacts.push(dest);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            for path in written_paths {
+                let Some(file_name) = Path::new(&path).file_name() else { continue };
+                let dest = dir.join(file_name);
+                if fs::copy(&path, &dest).is_ok() {
+                    artifacts.push(dest);
+                }
+            }
+
+            artifacts
+        });
+
+        Self { handle }
+    }
+
+    /// Detaches this collector's sink (closing its channel, which unblocks
+    /// its task) and returns every artifact path it saved.
+    async fn finish(self, agent: &Agent) -> Vec<PathBuf> {
+        agent.set_event_sender(None).await;
+        self.handle.await.unwrap_or_default()
+    }
+}
+
+/// Pumps a `WorkerChannel` over a TCP connection to a remote worker process:
+/// requests the coordinator queues go out as frames, and frames coming back
+/// are forwarded as `TaskUpdate`s, so `Coordinator::dispatch`/`run` don't need
+/// to know whether a worker is in-process or remote.
+///
+/// The two directions run as independent loops raced with `select!` rather
+/// than interleaved reads/writes on one `select!` iteration, since
+/// `AsyncBufReadExt::read_line` isn't cancellation-safe mid-frame: racing a
+/// single partial read against the request channel could drop bytes already
+/// consumed from the socket. Here, whichever loop exits first (channel closed
+/// or socket closed) just tears down the whole bridge, which is the correct
+/// behavior either way.
+async fn bridge_worker_socket(channel: WorkerChannel, stream: TcpStream) -> Result<()> {
+    let WorkerChannel { mut requests, updates, .. } = channel;
+    let (read_half, mut write_half) = split(stream);
+    let mut reader = BufReader::new(read_half);
+
+    let send_requests = async {
+        while let Some(task) = requests.recv().await {
+            write_frame(&mut write_half, &task).await?;
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+
+    let recv_updates = async {
+        while let Some(update) = read_frame::<_, TaskUpdate>(&mut reader).await? {
+            let _ = updates.send(update).await;
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+
+    tokio::select! {
+        res = send_requests => res,
+        res = recv_updates => res,
+    }
+}
+
+/// Worker-side entry point for a separate process: connects to the
+/// coordinator at `addr` and serves `RequestedTask`s read off the wire by
+/// running them through `agent.chat()`, reporting each `TaskUpdate` back over
+/// the same connection. Functionally identical to `Worker::run`, just over a
+/// real socket instead of an in-process channel.
+pub async fn run_remote_worker(agent: Arc<Agent>, addr: impl ToSocketAddrs) -> Result<()> {
+    let stream = TcpStream::connect(addr).await?;
+    let (read_half, mut write_half) = split(stream);
+    let mut reader = BufReader::new(read_half);
+
+    while let Some(task) = read_frame::<_, RequestedTask>(&mut reader).await? {
+        write_frame(
+            &mut write_half,
+            &TaskUpdate {
+                id: task.id,
+                state: TaskState::Running,
+                output: None,
+                artifacts: Vec::new(),
+            },
+        )
+        .await?;
+
+        let update = run_task(&agent, &task).await;
+        write_frame(&mut write_half, &update).await?;
+    }
+    Ok(())
+}
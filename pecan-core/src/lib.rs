@@ -1,6 +1,9 @@
 pub mod tools;
 pub mod memory;
 pub mod config;
+pub mod protocol;
+pub mod distributed;
+pub mod process;
 
 use pecan_providers::{Message, Provider, ChatCompletionRequest, Role, LlamaCppProvider, MockProvider};
 use crate::memory::MemoryManager;
@@ -13,6 +16,30 @@ use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// Rough token estimate for a string. Good enough for budgeting decisions
+/// without pulling in a full tokenizer: English text averages ~4 chars/token.
+pub fn estimate_tokens(text: &str) -> usize {
+    (text.chars().count() + 3) / 4
+}
+
+/// Estimated token footprint of a slice of messages.
+pub fn estimate_history_tokens(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|m| estimate_tokens(m.content.as_deref().unwrap_or("")))
+        .sum()
+}
+
+/// Truncates a string to `max` characters for compact UI display.
+pub fn truncate_for_display(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max).collect();
+        format!("{}…", truncated)
+    }
+}
+
 #[async_trait::async_trait]
 pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
@@ -21,14 +48,44 @@ pub trait Tool: Send + Sync {
     async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value>;
 }
 
+/// Outcome of a pre-hook: let the call `Proceed`, `Deny` it with a reason, or
+/// `Rewrite` its arguments to a new value before the tool runs.
+#[derive(Debug, Clone)]
+pub enum HookDecision {
+    Proceed,
+    Deny(String),
+    Rewrite(serde_json::Value),
+}
+
+/// A reusable side-channel that fires around any tool invocation, independent of
+/// the tool itself. Pre-hooks may mutate or veto arguments; post-hooks may
+/// rewrite or annotate results. Used for security checks, audit logging, PII
+/// redaction, rate limiting, and the like.
+#[async_trait::async_trait]
+pub trait ToolHook: Send + Sync {
+    async fn before(&self, tool_name: &str, args: &mut serde_json::Value) -> Result<HookDecision> {
+        let _ = (tool_name, args);
+        Ok(HookDecision::Proceed)
+    }
+
+    async fn after(&self, tool_name: &str, result: &mut serde_json::Value) -> Result<()> {
+        let _ = (tool_name, result);
+        Ok(())
+    }
+}
+
 pub struct ToolRegistry {
     pub tools: HashMap<String, Arc<dyn Tool>>,
+    pub pre_hooks: Vec<Arc<dyn ToolHook>>,
+    pub post_hooks: Vec<Arc<dyn ToolHook>>,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            pre_hooks: Vec::new(),
+            post_hooks: Vec::new(),
         }
     }
 
@@ -36,6 +93,14 @@ impl ToolRegistry {
         self.tools.insert(tool.name().to_string(), tool);
     }
 
+    pub fn register_pre_hook(&mut self, hook: Arc<dyn ToolHook>) {
+        self.pre_hooks.push(hook);
+    }
+
+    pub fn register_post_hook(&mut self, hook: Arc<dyn ToolHook>) {
+        self.post_hooks.push(hook);
+    }
+
     pub fn get_definitions(&self) -> Vec<serde_json::Value> {
         self.tools.values().map(|t| {
             serde_json::json!({
@@ -55,12 +120,89 @@ pub struct AgentState {
     pub history: Vec<Message>,
 }
 
+/// Explicit lifecycle of the agent, replacing the old `paused` boolean and the
+/// `"WAITING_FOR_APPROVAL"` sentinel. Callers (TUI/daemon) observe and gate the
+/// agent through this state rather than a grab-bag of ad-hoc flags.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentLifecycle {
+    Idle,
+    Thinking,
+    ExecutingTool(String),
+    WaitingForApproval,
+    Paused,
+    Failed(String),
+}
+
+impl AgentLifecycle {
+    /// Whether a direct transition from `self` to `to` is permitted.
+    pub fn can_transition_to(&self, to: &AgentLifecycle) -> bool {
+        use AgentLifecycle::*;
+        match (self, to) {
+            (a, b) if a == b => true,
+            // Pausing and failing may happen from any active state.
+            (_, Paused) | (_, Failed(_)) => true,
+            (Paused, Idle) | (Paused, Thinking) => true,
+            (Paused, _) => false,
+            (Idle, Thinking) => true,
+            (Idle, _) => false,
+            (Thinking, ExecutingTool(_)) | (Thinking, WaitingForApproval) | (Thinking, Idle) => true,
+            (Thinking, _) => false,
+            (ExecutingTool(_), Thinking) | (ExecutingTool(_), ExecutingTool(_)) | (ExecutingTool(_), Idle) => true,
+            // An approved call in a multi-call batch hands off to the next pending one.
+            (ExecutingTool(_), WaitingForApproval) => true,
+            (ExecutingTool(_), _) => false,
+            (WaitingForApproval, ExecutingTool(_)) | (WaitingForApproval, Thinking) | (WaitingForApproval, Idle) => true,
+            (WaitingForApproval, _) => false,
+            (Failed(_), Idle) => true,
+            (Failed(_), _) => false,
+        }
+    }
+}
+
+/// Incremental events emitted while a `chat` turn runs, so observers (the TUI)
+/// can watch the tool-calling chain unfold instead of only seeing the final text.
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    ToolStarted { name: String, arguments: serde_json::Value },
+    ToolFinished { name: String, output: String },
+    AwaitingApproval { tool_name: String, arguments: serde_json::Value },
+}
+
+/// Typed outcome of a `chat` turn, returned in place of magic sentinel strings.
+#[derive(Debug, Clone)]
+pub enum AgentStatus {
+    /// The model produced a final textual answer.
+    Response(String),
+    /// A tool call is queued and awaiting user approval.
+    AwaitingApproval { tool_name: String },
+}
+
+impl std::fmt::Display for AgentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentStatus::Response(text) => write!(f, "{}", text),
+            AgentStatus::AwaitingApproval { tool_name } => {
+                write!(f, "Awaiting approval for tool: {}", tool_name)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: Uuid,
     pub description: String,
     pub status: TaskStatus,
     pub created_at: DateTime<Utc>,
+    /// Ids of tasks that must be `Completed` before this one becomes ready.
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    /// When set, the task is re-armed after it completes instead of finishing.
+    #[serde(default)]
+    pub schedule: Option<Recurrence>,
+    /// Earliest time the task may run. `None` means "as soon as dependencies allow".
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -71,6 +213,52 @@ pub enum TaskStatus {
     Failed(String),
 }
 
+/// How a recurring task re-arms itself once it completes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Recurrence {
+    /// Fire again a fixed duration after the previous completion.
+    Interval(std::time::Duration),
+    /// Fire at a wall-clock time; unset fields default to zero / every day.
+    Cron {
+        minute: Option<u32>,
+        hour: Option<u32>,
+        day: Option<u32>,
+    },
+}
+
+impl Recurrence {
+    /// Computes the next fire time strictly after `now`.
+    pub fn next_fire_from(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        use chrono::{Datelike, Timelike};
+        match self {
+            Recurrence::Interval(d) => {
+                now + chrono::Duration::from_std(*d).unwrap_or_else(|_| chrono::Duration::seconds(1))
+            }
+            Recurrence::Cron { minute, hour, day } => {
+                let mut candidate = now
+                    .with_second(0)
+                    .and_then(|d| d.with_nanosecond(0))
+                    .and_then(|d| d.with_minute(minute.unwrap_or(0)))
+                    .and_then(|d| d.with_hour(hour.unwrap_or(0)))
+                    .unwrap_or(now);
+                if let Some(day) = day {
+                    candidate = candidate.with_day(*day).unwrap_or(candidate);
+                }
+                while candidate <= now {
+                    candidate = candidate + chrono::Duration::days(if day.is_some() { 30 } else { 1 });
+                    if let Some(day) = day {
+                        candidate = candidate.with_day(*day).unwrap_or(candidate);
+                    }
+                }
+                candidate
+            }
+        }
+    }
+}
+
+/// Dependency- and schedule-aware task scheduler. Still a flat list of `Task`s,
+/// but `next_ready` only hands out tasks whose dependencies are satisfied and
+/// whose fire time has arrived, and recurring tasks re-arm on completion.
 pub struct TaskStack {
     pub tasks: Vec<Task>,
 }
@@ -81,12 +269,27 @@ impl TaskStack {
     }
 
     pub fn push(&mut self, description: String) -> Uuid {
+        self.push_scheduled(description, Vec::new(), None)
+    }
+
+    /// Pushes a task with explicit dependencies and an optional recurrence.
+    pub fn push_scheduled(
+        &mut self,
+        description: String,
+        depends_on: Vec<Uuid>,
+        schedule: Option<Recurrence>,
+    ) -> Uuid {
         let id = Uuid::new_v4();
+        let now = Utc::now();
+        let next_run = schedule.as_ref().map(|s| s.next_fire_from(now));
         self.tasks.push(Task {
             id,
             description,
             status: TaskStatus::Pending,
-            created_at: Utc::now(),
+            created_at: now,
+            depends_on,
+            schedule,
+            next_run,
         });
         id
     }
@@ -96,6 +299,64 @@ impl TaskStack {
         Some(self.tasks.remove(idx))
     }
 
+    /// Returns the next runnable task — `Pending`, all dependencies `Completed`,
+    /// and fire time reached — marking it `InProgress` in place so recurring
+    /// tasks survive to be re-armed. Returns a clone of the selected task.
+    pub fn next_ready(&mut self) -> Option<Task> {
+        let now = Utc::now();
+        let completed: std::collections::HashSet<Uuid> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Completed)
+            .map(|t| t.id)
+            .collect();
+
+        let id = self
+            .tasks
+            .iter()
+            .find(|t| {
+                t.status == TaskStatus::Pending
+                    && t.depends_on.iter().all(|d| completed.contains(d))
+                    && t.next_run.map(|nr| nr <= now).unwrap_or(true)
+            })
+            .map(|t| t.id)?;
+
+        let task = self.tasks.iter_mut().find(|t| t.id == id)?;
+        task.status = TaskStatus::InProgress;
+        Some(task.clone())
+    }
+
+    /// Marks a task done. Recurring tasks are re-armed (next fire time computed,
+    /// status reset to `Pending`) rather than left `Completed`.
+    pub fn complete(&mut self, id: Uuid) {
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            match &task.schedule {
+                Some(schedule) => {
+                    task.next_run = Some(schedule.next_fire_from(Utc::now()));
+                    task.status = TaskStatus::Pending;
+                }
+                None => task.status = TaskStatus::Completed,
+            }
+        }
+    }
+
+    /// Earliest future fire time among pending tasks, used to sleep the loop
+    /// until a recurring task is due instead of exiting.
+    pub fn earliest_next_run(&self) -> Option<DateTime<Utc>> {
+        self.tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Pending)
+            .filter_map(|t| t.next_run)
+            .min()
+    }
+
+    /// Whether any task carries a recurrence and could fire again.
+    pub fn has_recurring(&self) -> bool {
+        self.tasks
+            .iter()
+            .any(|t| t.schedule.is_some() && t.status != TaskStatus::Completed)
+    }
+
     pub fn cancel_task(&mut self, id: Uuid) {
         if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
             task.status = TaskStatus::Failed("Cancelled by user".to_string());
@@ -124,6 +385,47 @@ pub struct PendingToolCall {
     pub arguments: serde_json::Value,
 }
 
+/// A batch of tool calls emitted by the model in a single turn that are awaiting
+/// user approval. Calls are resolved front-to-back; the accumulated `Role::Tool`
+/// results are kept in the original call order and only flushed into history once
+/// the whole batch has been approved or rejected.
+#[derive(Debug, Clone, Default)]
+pub struct PendingBatch {
+    pub queue: std::collections::VecDeque<PendingToolCall>,
+    pub resolved: Vec<Message>,
+}
+
+impl PendingBatch {
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// The call the user is currently being asked to approve, if any.
+    pub fn front(&self) -> Option<&PendingToolCall> {
+        self.queue.front()
+    }
+}
+
+/// Enforces the `allowed`/`blocked` shell-command policy from `ToolConfig` as a
+/// pre-hook, replacing the previously hard-coded check in the dispatch path.
+pub struct ShellSecurityHook {
+    pub config: Arc<Mutex<Config>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHook for ShellSecurityHook {
+    async fn before(&self, tool_name: &str, args: &mut serde_json::Value) -> Result<HookDecision> {
+        if tool_name != "shell" {
+            return Ok(HookDecision::Proceed);
+        }
+        let config = self.config.lock().await;
+        match Agent::check_shell_security(&config, args) {
+            Ok(()) => Ok(HookDecision::Proceed),
+            Err(e) => Ok(HookDecision::Deny(e.to_string())),
+        }
+    }
+}
+
 pub struct Agent {
     pub provider: Arc<Mutex<Arc<dyn Provider>>>,
     pub state: Arc<Mutex<AgentState>>,
@@ -131,17 +433,36 @@ pub struct Agent {
     pub memory: Arc<Mutex<MemoryManager>>,
     pub config: Arc<Mutex<Config>>,
     pub task_stack: Arc<Mutex<TaskStack>>,
-    pub paused: Arc<Mutex<bool>>,
-    pub pending_tool_call: Arc<Mutex<Option<PendingToolCall>>>,
+    pub lifecycle: Arc<Mutex<AgentLifecycle>>,
+    pub pending_batch: Arc<Mutex<PendingBatch>>,
+    pub event_tx: Arc<Mutex<Option<tokio::sync::mpsc::UnboundedSender<AgentEvent>>>>,
+    pub processes: Arc<Mutex<HashMap<Uuid, process::ProcessHandle>>>,
+    /// Remote dispatch target for `run_autonomous_loop`, set via
+    /// `set_coordinator`. When present, ready tasks are handed to a connected
+    /// worker over the wire protocol instead of run in-process; absent (the
+    /// default) every task runs locally, unchanged from before distributed
+    /// mode existed.
+    pub coordinator: Arc<Mutex<Option<Arc<Mutex<distributed::Coordinator>>>>>,
 }
 
 impl Agent {
     pub async fn new(config: Config, memory_path: &str) -> Result<Self> {
-        let provider: Arc<dyn Provider> = Self::create_provider(&config, &config.default_model)?;
+        let default_model = config.default_model.clone();
+        Self::new_with_provider(config, &default_model, memory_path).await
+    }
+
+    /// Like `new`, but selects the provider by name instead of
+    /// `config.default_model` — looked up in `config.providers` first, then
+    /// falling back to `config.models`. Lets callers (e.g. the server's
+    /// `CreateSessionRequest.provider`) pick a provider per session.
+    pub async fn new_with_provider(config: Config, provider_name: &str, memory_path: &str) -> Result<Self> {
+        let provider: Arc<dyn Provider> = Self::create_provider(&config, provider_name)?;
+        let config = Arc::new(Mutex::new(config));
         let task_stack = Arc::new(Mutex::new(TaskStack::new()));
         let tools = Arc::new(Mutex::new(ToolRegistry::new()));
-        let paused = Arc::new(Mutex::new(false));
-        let pending_tool_call = Arc::new(Mutex::new(None));
+        let lifecycle = Arc::new(Mutex::new(AgentLifecycle::Idle));
+        let pending_batch = Arc::new(Mutex::new(PendingBatch::default()));
+        let processes = Arc::new(Mutex::new(HashMap::new()));
 
         {
             let mut registry = tools.lock().await;
@@ -151,24 +472,39 @@ impl Agent {
             registry.register(Arc::new(tools::SpawnSubagent));
             registry.register(Arc::new(tools::PushTask { stack: task_stack.clone() }));
             registry.register(Arc::new(tools::Shell));
+            registry.register(Arc::new(tools::StartProcess { processes: processes.clone() }));
+            registry.register(Arc::new(tools::WriteStdin { processes: processes.clone() }));
+            registry.register(Arc::new(tools::ReadOutput { processes: processes.clone() }));
+            registry.register(Arc::new(tools::KillProcess { processes: processes.clone() }));
+            registry.register_pre_hook(Arc::new(ShellSecurityHook { config: config.clone() }));
         }
-        
+
+        let mut memory = MemoryManager::new(memory_path)?;
+        memory.set_embedder(provider.clone());
+
         Ok(Self {
             provider: Arc::new(Mutex::new(provider)),
             state: Arc::new(Mutex::new(AgentState { history: Vec::new() })),
             tools,
-            memory: Arc::new(Mutex::new(MemoryManager::new(memory_path)?)),
-            config: Arc::new(Mutex::new(config)),
+            memory: Arc::new(Mutex::new(memory)),
+            config,
             task_stack,
-            paused,
-            pending_tool_call,
+            lifecycle,
+            pending_batch,
+            event_tx: Arc::new(Mutex::new(None)),
+            processes,
+            coordinator: Arc::new(Mutex::new(None)),
         })
     }
 
     fn create_provider(config: &Config, model_name: &str) -> Result<Arc<dyn Provider>> {
+        if let Some(provider_config) = config.providers.get(model_name) {
+            return Ok(provider_config.init());
+        }
+
         let model = config.models.get(model_name)
             .ok_or_else(|| anyhow::anyhow!("Model {} not found in config", model_name))?;
-        
+
         match model.provider.as_str() {
             "llama.cpp" => Ok(Arc::new(LlamaCppProvider { url: model.url.clone() })),
             "openai" => Ok(Arc::new(pecan_providers::OpenAiProvider { 
@@ -190,6 +526,59 @@ impl Agent {
         Ok(())
     }
 
+    /// Registers (or clears) the sink that receives per-step [`AgentEvent`]s.
+    pub async fn set_event_sender(&self, tx: Option<tokio::sync::mpsc::UnboundedSender<AgentEvent>>) {
+        *self.event_tx.lock().await = tx;
+    }
+
+    /// Registers (or clears) the [`distributed::Coordinator`] `run_autonomous_loop`
+    /// dispatches ready tasks to.
+    pub async fn set_coordinator(&self, coordinator: Option<Arc<Mutex<distributed::Coordinator>>>) {
+        *self.coordinator.lock().await = coordinator;
+    }
+
+    async fn emit_event(&self, event: AgentEvent) {
+        if let Some(tx) = self.event_tx.lock().await.as_ref() {
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Returns a snapshot of the current lifecycle state.
+    pub async fn lifecycle(&self) -> AgentLifecycle {
+        self.lifecycle.lock().await.clone()
+    }
+
+    /// Convenience for callers that only care whether the agent is paused.
+    pub async fn is_paused(&self) -> bool {
+        matches!(*self.lifecycle.lock().await, AgentLifecycle::Paused)
+    }
+
+    /// Drives the lifecycle to `to`, validating the edge and logging the change.
+    async fn transition(&self, to: AgentLifecycle) -> Result<()> {
+        let mut current = self.lifecycle.lock().await;
+        if !current.can_transition_to(&to) {
+            anyhow::bail!("Illegal lifecycle transition: {:?} -> {:?}", *current, to);
+        }
+        tracing::info!("Agent lifecycle: {:?} -> {:?}", *current, to);
+        *current = to;
+        Ok(())
+    }
+
+    /// Pauses the autonomous loop. Legal from any state.
+    pub async fn pause(&self) -> Result<()> {
+        self.transition(AgentLifecycle::Paused).await
+    }
+
+    /// Resumes from a paused state back to idle. Only legal while `Paused`.
+    pub async fn resume(&self) -> Result<()> {
+        let current = self.lifecycle.lock().await.clone();
+        if current != AgentLifecycle::Paused {
+            anyhow::bail!("Cannot resume: agent is not paused (state: {:?})", current);
+        }
+        drop(current);
+        self.transition(AgentLifecycle::Idle).await
+    }
+
     fn check_shell_security(config: &Config, arguments: &serde_json::Value) -> Result<()> {
         let command = arguments["command"].as_str().ok_or_else(|| anyhow::anyhow!("Missing command"))?;
         
@@ -205,73 +594,261 @@ impl Agent {
         Ok(())
     }
 
-    pub async fn approve_tool_call(&self) -> Result<String> {
-        let pending = {
-            let mut p = self.pending_tool_call.lock().await;
-            p.take()
+    pub async fn approve_tool_call(&self) -> Result<AgentStatus> {
+        let next = {
+            let mut batch = self.pending_batch.lock().await;
+            batch.queue.pop_front()
         };
 
-        if let Some(p) = pending {
-            if p.tool_name == "shell" {
-                let config = self.config.lock().await;
-                if let Err(e) = Self::check_shell_security(&config, &p.arguments) {
-                    return Err(e);
-                }
+        let p = match next {
+            Some(p) => p,
+            None => anyhow::bail!("No pending tool call to approve"),
+        };
+
+        {
+            let current = self.lifecycle.lock().await.clone();
+            if current != AgentLifecycle::WaitingForApproval {
+                anyhow::bail!("Cannot approve tool call: agent is not waiting for approval (state: {:?})", current);
             }
+        }
+        self.transition(AgentLifecycle::ExecutingTool(p.tool_name.clone())).await?;
+        self.emit_event(AgentEvent::ToolStarted {
+            name: p.tool_name.clone(),
+            arguments: p.arguments.clone(),
+        })
+        .await;
 
-            let result = {
-                let tools = self.tools.lock().await;
-                if let Some(tool) = tools.tools.get(&p.tool_name) {
-                    tool.call(p.arguments).await?
-                } else {
-                    serde_json::json!({ "error": format!("Tool {} not found", p.tool_name) })
-                }
+        let message = Self::dispatch_tool_call(self.tools.clone(), p.tool_name.clone(), p.id, p.arguments).await;
+        self.emit_event(AgentEvent::ToolFinished {
+            name: p.tool_name,
+            output: truncate_for_display(message.content.as_deref().unwrap_or(""), 240),
+        })
+        .await;
+        self.advance_pending_batch(message).await
+    }
+
+    pub async fn reject_tool_call(&self, reason: &str) -> Result<AgentStatus> {
+        let next = {
+            let mut batch = self.pending_batch.lock().await;
+            batch.queue.pop_front()
+        };
+
+        let p = match next {
+            Some(p) => p,
+            None => anyhow::bail!("No pending tool call to reject"),
+        };
+
+        {
+            let current = self.lifecycle.lock().await.clone();
+            if current != AgentLifecycle::WaitingForApproval {
+                anyhow::bail!("Cannot reject tool call: agent is not waiting for approval (state: {:?})", current);
+            }
+        }
+
+        let message = Message {
+            role: Role::Tool,
+            content: Some(serde_json::json!({ "error": "User rejected tool execution", "reason": reason }).to_string()),
+            tool_calls: None,
+            tool_call_id: Some(p.id),
+        };
+
+        self.advance_pending_batch(message).await
+    }
+
+    /// Records the result of the just-resolved pending call and either surfaces
+    /// the next call awaiting approval or, once the whole batch is resolved,
+    /// flushes the accumulated results into history and re-queries the model.
+    async fn advance_pending_batch(&self, message: Message) -> Result<AgentStatus> {
+        let next_pending = {
+            let mut batch = self.pending_batch.lock().await;
+            batch.resolved.push(message);
+            batch.queue.front().map(|p| p.tool_name.clone())
+        };
+
+        if let Some(tool_name) = next_pending {
+            self.transition(AgentLifecycle::WaitingForApproval).await?;
+            let arguments = {
+                let batch = self.pending_batch.lock().await;
+                batch.front().map(|p| p.arguments.clone()).unwrap_or(serde_json::Value::Null)
             };
+            self.emit_event(AgentEvent::AwaitingApproval {
+                tool_name: tool_name.clone(),
+                arguments,
+            })
+            .await;
+            return Ok(AgentStatus::AwaitingApproval { tool_name });
+        }
 
-            {
-                let mut state = self.state.lock().await;
-                state.history.push(Message {
+        let resolved = {
+            let mut batch = self.pending_batch.lock().await;
+            std::mem::take(&mut batch.resolved)
+        };
+
+        {
+            let mut state = self.state.lock().await;
+            state.history.extend(resolved);
+        }
+
+        self.transition(AgentLifecycle::Thinking).await?;
+        self.chat_internal().await
+    }
+
+    /// Executes every call in `tool_calls` concurrently on a bounded worker pool
+    /// and returns the resulting `Role::Tool` messages in the original call order.
+    async fn execute_tool_calls(&self, tool_calls: Vec<pecan_providers::ToolCall>) -> Vec<Message> {
+        let pool_size = {
+            let config = self.config.lock().await;
+            config.tools.parallel_pool_size()
+        };
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(pool_size));
+
+        for tool_call in &tool_calls {
+            let arguments = serde_json::from_str(&tool_call.function.arguments)
+                .unwrap_or_else(|_| serde_json::Value::String(tool_call.function.arguments.clone()));
+            self.emit_event(AgentEvent::ToolStarted {
+                name: tool_call.function.name.clone(),
+                arguments,
+            })
+            .await;
+        }
+
+        // Kept alongside each slot so a spawned task that panics (rather than
+        // returning its usual `Role::Tool` message) can still be answered
+        // under its real tool_call_id, and reported under its real name.
+        let metadata: Vec<(String, String)> = tool_calls
+            .iter()
+            .map(|tc| (tc.id.clone(), tc.function.name.clone()))
+            .collect();
+
+        let mut join_set = tokio::task::JoinSet::new();
+        for (idx, tool_call) in tool_calls.into_iter().enumerate() {
+            let tools = self.tools.clone();
+            let semaphore = semaphore.clone();
+            join_set.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let message = Self::run_single_tool_call(tools, tool_call).await;
+                (idx, message)
+            });
+        }
+
+        let mut results: Vec<Option<Message>> = Vec::new();
+        results.resize_with(metadata.len(), || None);
+        while let Some(joined) = join_set.join_next().await {
+            match joined {
+                Ok((idx, message)) => results[idx] = Some(message),
+                Err(e) => tracing::error!("Tool call task panicked: {}", e),
+            }
+        }
+
+        // A panicked task still owes its tool_call_id a `Role::Tool` reply —
+        // leaving it unanswered would make providers that require one result
+        // per tool_call reject the next request.
+        let messages: Vec<Message> = results
+            .into_iter()
+            .enumerate()
+            .map(|(idx, message)| {
+                message.unwrap_or_else(|| Message {
                     role: Role::Tool,
-                    content: Some(result.to_string()),
+                    content: Some(serde_json::json!({ "error": "Tool call task panicked" }).to_string()),
                     tool_calls: None,
-                    tool_call_id: Some(p.id),
-                });
-            }
+                    tool_call_id: Some(metadata[idx].0.clone()),
+                })
+            })
+            .collect();
 
-            self.chat_loop_continue().await
-        } else {
-            anyhow::bail!("No pending tool call to approve")
+        for (idx, message) in messages.iter().enumerate() {
+            let output = message.content.clone().unwrap_or_default();
+            self.emit_event(AgentEvent::ToolFinished {
+                name: metadata[idx].1.clone(),
+                output: truncate_for_display(&output, 240),
+            })
+            .await;
         }
+
+        messages
     }
 
-    pub async fn reject_tool_call(&self, reason: &str) -> Result<String> {
-        let pending = {
-            let mut p = self.pending_tool_call.lock().await;
-            p.take()
-        };
+    /// Runs one tool call, turning argument-parsing, security, and execution
+    /// failures into `Role::Tool` error messages so a single bad call never
+    /// aborts the rest of the batch.
+    async fn run_single_tool_call(
+        tools: Arc<Mutex<ToolRegistry>>,
+        tool_call: pecan_providers::ToolCall,
+    ) -> Message {
+        let tool_name = tool_call.function.name.clone();
+        let id = tool_call.id.clone();
 
-        if let Some(p) = pending {
-            {
-                let mut state = self.state.lock().await;
-                state.history.push(Message {
+        let arguments: serde_json::Value = match serde_json::from_str(&tool_call.function.arguments) {
+            Ok(v) => v,
+            Err(e) => {
+                return Message {
                     role: Role::Tool,
-                    content: Some(serde_json::json!({ "error": "User rejected tool execution", "reason": reason }).to_string()),
+                    content: Some(serde_json::json!({ "error": format!("Invalid tool arguments: {}", e) }).to_string()),
                     tool_calls: None,
-                    tool_call_id: Some(p.id),
-                });
+                    tool_call_id: Some(id),
+                };
             }
+        };
 
-            self.chat_loop_continue().await
-        } else {
-            anyhow::bail!("No pending tool call to reject")
-        }
+        Self::dispatch_tool_call(tools, tool_name, id, arguments).await
     }
 
-    async fn chat_loop_continue(&self) -> Result<String> {
-        self.chat_internal().await
+    /// Shared dispatch path for both the approval and direct-execution branches:
+    /// runs pre-hooks (which may rewrite or veto `arguments`), invokes the tool,
+    /// then runs post-hooks (which may rewrite or annotate the result), always
+    /// returning a `Role::Tool` message.
+    async fn dispatch_tool_call(
+        tools: Arc<Mutex<ToolRegistry>>,
+        tool_name: String,
+        id: String,
+        mut arguments: serde_json::Value,
+    ) -> Message {
+        let make = |value: serde_json::Value| Message {
+            role: Role::Tool,
+            content: Some(value.to_string()),
+            tool_calls: None,
+            tool_call_id: Some(id.clone()),
+        };
+
+        tracing::info!("Executing tool: {} with args: {}", tool_name, arguments);
+
+        let (pre_hooks, post_hooks) = {
+            let registry = tools.lock().await;
+            (registry.pre_hooks.clone(), registry.post_hooks.clone())
+        };
+
+        for hook in &pre_hooks {
+            match hook.before(&tool_name, &mut arguments).await {
+                Ok(HookDecision::Proceed) => {}
+                Ok(HookDecision::Rewrite(new_args)) => arguments = new_args,
+                Ok(HookDecision::Deny(reason)) => return make(serde_json::json!({ "error": reason })),
+                Err(e) => return make(serde_json::json!({ "error": e.to_string() })),
+            }
+        }
+
+        let mut result = {
+            let registry = tools.lock().await;
+            match registry.tools.get(&tool_name) {
+                Some(tool) => match tool.call(arguments).await {
+                    Ok(value) => value,
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                },
+                None => serde_json::json!({ "error": format!("Tool {} not found", tool_name) }),
+            }
+        };
+
+        for hook in &post_hooks {
+            if let Err(e) = hook.after(&tool_name, &mut result).await {
+                tracing::warn!("Post-hook failed for {}: {}", tool_name, e);
+            }
+        }
+
+        make(result)
     }
 
-    pub async fn chat(&self, user_input: String) -> Result<String> {
+    pub async fn chat(&self, user_input: String) -> Result<AgentStatus> {
+        self.transition(AgentLifecycle::Thinking).await?;
+
         let memories = {
             let memory = self.memory.lock().await;
             memory.search(&user_input, 5)?
@@ -303,10 +880,38 @@ impl Agent {
         self.chat_internal().await
     }
 
-    async fn chat_internal(&self) -> Result<String> {
-        let mut final_response = String::new();
+    /// Like `chat`, but replaces the live history with `messages` wholesale
+    /// instead of appending a single user turn. Used by stateless callers
+    /// (e.g. the OpenAI-compatible `/v1/chat/completions` endpoint) that
+    /// send their full conversation on every request.
+    pub async fn chat_with_history(&self, messages: Vec<Message>) -> Result<AgentStatus> {
+        self.transition(AgentLifecycle::Thinking).await?;
+
+        {
+            let mut state = self.state.lock().await;
+            state.history = messages;
+        }
+        self.chat_internal().await
+    }
+
+    async fn chat_internal(&self) -> Result<AgentStatus> {
+        let final_response;
+        let max_steps = { self.config.lock().await.tools.max_tool_steps };
+        let mut steps = 0;
 
         loop {
+            steps += 1;
+            if steps > max_steps {
+                tracing::warn!("Hit max_tool_steps ({}) without a final response", max_steps);
+                final_response = format!(
+                    "Stopped after {} tool-calling steps without a final answer.",
+                    max_steps
+                );
+                break;
+            }
+
+            self.maybe_compact_history().await?;
+
             let (messages, tool_definitions) = {
                 let state = self.state.lock().await;
                 let tools = self.tools.lock().await;
@@ -338,58 +943,52 @@ impl Agent {
 
             if let Some(tool_calls) = response.tool_calls {
                 if !tool_calls.is_empty() {
-                    let config = self.config.lock().await;
-                    let require_approval = config.tools.require_approval;
-                    
-                    if require_approval {
-                        tracing::info!("Tool approval required for {}", tool_calls[0].function.name);
-                        let tool_call = &tool_calls[0];
-                        let mut p = self.pending_tool_call.lock().await;
-                        *p = Some(PendingToolCall {
-                            id: tool_call.id.clone(),
-                            tool_name: tool_call.function.name.clone(),
-                            arguments: serde_json::from_str(&tool_call.function.arguments)?,
-                        });
-                        
-                        return Ok("WAITING_FOR_APPROVAL".to_string());
-                    }
+                    let require_approval = {
+                        let config = self.config.lock().await;
+                        config.tools.require_approval
+                    };
 
-                    for tool_call in tool_calls {
-                        let tool_name = &tool_call.function.name;
-                        let arguments: serde_json::Value = serde_json::from_str(&tool_call.function.arguments)?;
-                        
-                        tracing::info!("Executing tool: {} with args: {}", tool_name, arguments);
-
-                        if tool_name == "shell" {
-                            if let Err(e) = Self::check_shell_security(&config, &arguments) {
-                                let mut state = self.state.lock().await;
-                                state.history.push(Message {
-                                    role: Role::Tool,
-                                    content: Some(serde_json::json!({ "error": e.to_string() }).to_string()),
-                                    tool_calls: None,
-                                    tool_call_id: Some(tool_call.id.clone()),
+                    if require_approval {
+                        tracing::info!("Tool approval required for {} call(s)", tool_calls.len());
+                        let first_tool = tool_calls[0].function.name.clone();
+                        {
+                            let mut batch = self.pending_batch.lock().await;
+                            *batch = PendingBatch::default();
+                            for tool_call in &tool_calls {
+                                // Malformed JSON here shouldn't abort the whole
+                                // turn - fall back to the raw string, same as
+                                // the non-approval path's `ToolStarted` event
+                                // (lib.rs ~704), and let the tool's own
+                                // argument validation turn it into an error
+                                // `Role::Tool` message once approved.
+                                let arguments = serde_json::from_str(&tool_call.function.arguments)
+                                    .unwrap_or_else(|_| serde_json::Value::String(tool_call.function.arguments.clone()));
+                                batch.queue.push_back(PendingToolCall {
+                                    id: tool_call.id.clone(),
+                                    tool_name: tool_call.function.name.clone(),
+                                    arguments,
                                 });
-                                continue;
                             }
                         }
 
-                        let result = {
-                            let tools = self.tools.lock().await;
-                            if let Some(tool) = tools.tools.get(tool_name) {
-                                tool.call(arguments).await?
-                            } else {
-                                serde_json::json!({ "error": format!("Tool {} not found", tool_name) })
-                            }
-                        };
+                        self.transition(AgentLifecycle::WaitingForApproval).await?;
+                        let arguments = serde_json::from_str(&tool_calls[0].function.arguments)
+                            .unwrap_or(serde_json::Value::Null);
+                        self.emit_event(AgentEvent::AwaitingApproval {
+                            tool_name: first_tool.clone(),
+                            arguments,
+                        })
+                        .await;
+                        return Ok(AgentStatus::AwaitingApproval { tool_name: first_tool });
+                    }
 
+                    self.transition(AgentLifecycle::ExecutingTool(tool_calls[0].function.name.clone())).await?;
+                    let results = self.execute_tool_calls(tool_calls).await;
+                    {
                         let mut state = self.state.lock().await;
-                        state.history.push(Message {
-                            role: Role::Tool,
-                            content: Some(result.to_string()),
-                            tool_calls: None,
-                            tool_call_id: Some(tool_call.id),
-                        });
+                        state.history.extend(results);
                     }
+                    self.transition(AgentLifecycle::Thinking).await?;
                     continue;
                 }
             }
@@ -398,8 +997,127 @@ impl Agent {
             tracing::info!("Model returned final response: {}", final_response);
             break;
         }
-        
-        Ok(final_response)
+
+        self.transition(AgentLifecycle::Idle).await?;
+        Ok(AgentStatus::Response(final_response))
+    }
+
+    /// Folds the oldest non-system turns into a single `Role::System` summary
+    /// once the history exceeds `config.memory.context_token_budget`, preserving
+    /// recent turns and any message tied to an unresolved `tool_call_id`. The
+    /// summarized-away transcript is also persisted to long-term memory so it
+    /// remains retrievable via `search`.
+    async fn maybe_compact_history(&self) -> Result<()> {
+        let budget = { self.config.lock().await.memory.context_token_budget };
+        if budget == 0 {
+            return Ok(());
+        }
+
+        let history = { self.state.lock().await.history.clone() };
+        if estimate_history_tokens(&history) <= budget {
+            return Ok(());
+        }
+
+        // Tool calls answered by a later `Role::Tool` message are resolved;
+        // anything still outstanding must stay in the preserved tail.
+        let answered: std::collections::HashSet<&str> =
+            history.iter().filter_map(|m| m.tool_call_id.as_deref()).collect();
+        let has_unresolved_call = |m: &Message| {
+            m.tool_calls
+                .as_ref()
+                .map(|calls| calls.iter().any(|c| !answered.contains(c.id.as_str())))
+                .unwrap_or(false)
+        };
+
+        // Keep the most recent turns up to about half the budget.
+        let keep_target = budget / 2;
+        let mut keep_from = history.len();
+        let mut accumulated = 0usize;
+        for (i, m) in history.iter().enumerate().rev() {
+            accumulated += estimate_tokens(m.content.as_deref().unwrap_or(""));
+            keep_from = i;
+            if accumulated >= keep_target {
+                break;
+            }
+        }
+        while keep_from > 0 && has_unresolved_call(&history[keep_from - 1]) {
+            keep_from -= 1;
+        }
+        if keep_from == 0 {
+            return Ok(());
+        }
+
+        let (old, recent) = history.split_at(keep_from);
+
+        // Keep leading system prompts verbatim; summarize everything after them.
+        let mut preserved_system: Vec<Message> = Vec::new();
+        let mut to_summarize: Vec<&Message> = Vec::new();
+        for m in old {
+            if m.role == Role::System && to_summarize.is_empty() {
+                preserved_system.push(m.clone());
+            } else {
+                to_summarize.push(m);
+            }
+        }
+        if to_summarize.is_empty() {
+            return Ok(());
+        }
+
+        let transcript = to_summarize
+            .iter()
+            .map(|m| format!("{:?}: {}", m.role, m.content.clone().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let summary = self
+            .summarize_text(&transcript)
+            .await
+            .unwrap_or_else(|_| "Earlier conversation omitted.".to_string());
+
+        {
+            let mut memory = self.memory.lock().await;
+            let _ = memory.add_memory(&transcript, &summary).await;
+        }
+
+        tracing::info!("Compacted {} old turns into a summary", to_summarize.len());
+
+        let mut new_history = preserved_system;
+        new_history.push(Message {
+            role: Role::System,
+            content: Some(format!("Summary of earlier conversation:\n{}", summary)),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+        new_history.extend(recent.iter().cloned());
+
+        let mut state = self.state.lock().await;
+        state.history = new_history;
+        Ok(())
+    }
+
+    /// Summarizes an arbitrary block of conversation text at low temperature.
+    async fn summarize_text(&self, text: &str) -> Result<String> {
+        let prompt = format!(
+            "Summarize the following conversation excerpt into a concise paragraph \
+            that preserves the key facts, decisions, and open threads:\n\n{}",
+            text
+        );
+
+        let request = ChatCompletionRequest {
+            messages: vec![Message {
+                role: Role::User,
+                content: Some(prompt),
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: Some(0.2),
+            max_tokens: Some(512),
+            tools: None,
+        };
+
+        let provider = self.provider.lock().await;
+        let response = provider.chat_completion(request).await?;
+        Ok(response.content.unwrap_or_else(|| "Conversation summary".to_string()))
     }
 
     async fn summarize_interaction(&self, user_input: &str, assistant_response: &str) -> Result<String> {
@@ -427,34 +1145,99 @@ impl Agent {
 
     pub async fn run_autonomous_loop(&self) -> Result<()> {
         loop {
-            {
-                let paused = self.paused.lock().await;
-                if *paused {
-                    drop(paused);
-                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                    continue;
-                }
+            if self.is_paused().await {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                continue;
             }
 
             let next_task = {
                 let mut stack = self.task_stack.lock().await;
-                stack.pop()
+                stack.next_ready()
             };
 
             let task = match next_task {
                 Some(t) => t,
-                None => break, 
+                None => {
+                    // Nothing ready right now: sleep until the earliest recurring
+                    // task is due, or exit if no more work can ever fire.
+                    let wake_at = {
+                        let stack = self.task_stack.lock().await;
+                        stack.earliest_next_run()
+                    };
+                    match wake_at {
+                        Some(at) => {
+                            let wait = (at - Utc::now())
+                                .to_std()
+                                .unwrap_or_else(|_| std::time::Duration::from_millis(500));
+                            tokio::time::sleep(wait.min(std::time::Duration::from_secs(60))).await;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
             };
 
+            // Prefer a connected remote worker when one is configured: hand the
+            // task off over the wire protocol and let `Coordinator::run`
+            // (driven elsewhere) apply the eventual `TaskUpdate`, leaving this
+            // task `InProgress` rather than running it in-process.
+            let coordinator = self.coordinator.lock().await.clone();
+            if let Some(coordinator) = coordinator {
+                let requested = protocol::RequestedTask {
+                    id: task.id,
+                    description: task.description.clone(),
+                    context: None,
+                    artifact_dir: None,
+                };
+                match coordinator.lock().await.dispatch(requested).await {
+                    Ok(worker_id) => {
+                        tracing::info!("Dispatched task {} to remote worker {}", task.id, worker_id);
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!("No remote worker available ({}), running task {} locally", e, task.id);
+                    }
+                }
+            }
+
             let prompt = format!(
                 "Current Task: {}\n\nExecute the next step for this task using available tools. \
                 If the task is finished, explain what you did. \
-                If you need to break it down further, you can use the 'push_task' tool.", 
+                If you need to break it down further, you can use the 'push_task' tool.",
                 task.description
             );
-            
-            let _response = self.chat(prompt).await?;
+
+            let outcome = match self.chat(prompt).await {
+                Ok(status) => self.auto_approve_until_response(status).await,
+                Err(e) => Err(e),
+            };
+
+            match outcome {
+                Ok(_) => {
+                    let mut stack = self.task_stack.lock().await;
+                    stack.complete(task.id);
+                }
+                Err(e) => {
+                    let mut stack = self.task_stack.lock().await;
+                    stack.update_status(task.id, TaskStatus::Failed(e.to_string()));
+                }
+            }
         }
         Ok(())
     }
+
+    /// Autonomous runs have nobody to answer an interactive approval prompt,
+    /// so every tool call the model emits is auto-approved in sequence until
+    /// a final `Response` comes back (or approval itself errors).
+    async fn auto_approve_until_response(&self, status: AgentStatus) -> Result<AgentStatus> {
+        let mut status = status;
+        loop {
+            match status {
+                AgentStatus::Response(_) => return Ok(status),
+                AgentStatus::AwaitingApproval { .. } => {
+                    status = self.approve_tool_call().await?;
+                }
+            }
+        }
+    }
 }
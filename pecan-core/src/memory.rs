@@ -3,9 +3,12 @@ use anyhow::Result;
 use std::fs::{self, OpenOptions};
 use std::io::{self, BufRead, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::collections::HashMap;
 use rusqlite::{params, Connection};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use pecan_providers::Provider;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MemoryOp {
@@ -14,15 +17,130 @@ pub enum MemoryOp {
         content: String,
         summary: String,
         timestamp: DateTime<Utc>,
+        /// Precomputed embedding, if an embedder was configured at write time.
+        /// Carried in the log so `sync_index` can rebuild `memory_vectors`
+        /// without an embedder on hand (e.g. replaying offline).
+        #[serde(default)]
+        embedding: Option<Vec<f32>>,
+        /// Structured facts attached to the memory (e.g. `priority: 3`,
+        /// `due: "2026-01-01T00:00:00Z"`), coerced through `Conversion` at
+        /// write time so `search_filtered` can compare against them typed
+        /// rather than as opaque strings.
+        #[serde(default)]
+        attributes: HashMap<String, serde_json::Value>,
     },
     Forget {
         id: Uuid,
     },
+    /// In-place edit of a memory's text without a forget+re-add round trip.
+    /// Attributes are untouched; use a new `Add` for those.
+    Update {
+        id: Uuid,
+        content: String,
+        summary: String,
+    },
+}
+
+/// Declares how a caller-supplied string should be coerced before it's
+/// stored as (or compared against) a memory attribute, so `priority` can be
+/// compared numerically and `due` can be compared as a timestamp instead of
+/// everything collapsing to string equality.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Conversion {
+    Bytes,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp { format: Option<String> },
+}
+
+impl Conversion {
+    pub fn parse(&self, raw: &str) -> Result<serde_json::Value> {
+        Ok(match self {
+            Conversion::Bytes | Conversion::String => serde_json::Value::String(raw.to_string()),
+            Conversion::Integer => serde_json::json!(raw.parse::<i64>()?),
+            Conversion::Float => serde_json::json!(raw.parse::<f64>()?),
+            Conversion::Boolean => serde_json::Value::Bool(raw.parse::<bool>()?),
+            Conversion::Timestamp { format: Some(fmt) } => {
+                let naive = chrono::NaiveDateTime::parse_from_str(raw, fmt)?;
+                serde_json::Value::String(naive.and_utc().to_rfc3339())
+            }
+            Conversion::Timestamp { format: None } => {
+                let ts = DateTime::parse_from_rfc3339(raw)?.with_timezone(&Utc);
+                serde_json::Value::String(ts.to_rfc3339())
+            }
+        })
+    }
+}
+
+/// Converts a coerced attribute value into a `rusqlite` parameter so it can
+/// be compared against `json_extract(attributes, '$.key')` with the right
+/// type affinity (numbers stay numbers, booleans fold to SQLite's 0/1).
+fn attribute_to_sql(value: serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as SqlValue;
+    match value {
+        serde_json::Value::String(s) => SqlValue::Text(s),
+        serde_json::Value::Bool(b) => SqlValue::Integer(b as i64),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(0.0))),
+        _ => SqlValue::Null,
+    }
 }
 
+/// Attribute keys are caller-supplied and, unlike their values, aren't bound
+/// as query parameters - `search_filtered` splices them straight into the
+/// `json_extract(...)` path expression. Attributes are free-form (there's no
+/// fixed schema to map a key onto a column), so the key is validated against
+/// this allowlist of characters instead, closing off the injection shape
+/// entirely rather than trying to enumerate every legitimate key.
+fn validate_attribute_key(key: &str) -> Result<()> {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(())
+    } else {
+        anyhow::bail!("Invalid attribute key '{}': only alphanumeric characters and '_' are allowed", key)
+    }
+}
+
+/// Packs a vector of `f32`s into a flat little-endian byte blob for storage
+/// in the `memory_vectors.vector` column.
+fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Default delta-log size (bytes) at which `add_memory` triggers an automatic
+/// `compact()`. Overridable via `set_compaction_threshold_bytes`.
+const DEFAULT_COMPACTION_THRESHOLD_BYTES: u64 = 4 * 1024 * 1024;
+
 pub struct MemoryManager {
+    base_path: String,
     log_path: String,
     db_conn: Connection,
+    /// Computes embeddings for `add_memory`. Optional: when absent, memories
+    /// are still logged and FTS-indexed, they just have no vector to rank by.
+    embedder: Option<Arc<dyn Provider>>,
+    compaction_threshold_bytes: u64,
 }
 
 impl MemoryManager {
@@ -34,17 +152,100 @@ impl MemoryManager {
         let mut db_conn = Connection::open(db_path)?;
         Self::setup_db(&mut db_conn)?;
 
+        // The DB's `meta.generation` is the source of truth for which
+        // snapshot generation is current; anything else (orphaned `.tmp`
+        // files from a compaction that died mid-write, snapshot files from a
+        // generation that was never committed) is safe to discard.
+        let generation = Self::read_generation(&db_conn)?;
+        Self::cleanup_orphans(base_path, generation)?;
+
         let mut manager = Self {
+            base_path: base_path.to_string(),
             log_path,
             db_conn,
+            embedder: None,
+            compaction_threshold_bytes: DEFAULT_COMPACTION_THRESHOLD_BYTES,
         };
 
-        // Bootstrap: Sync SQLite with log
+        // Bootstrap: Sync SQLite with the latest snapshot plus the delta log on top
         manager.sync_index()?;
 
         Ok(manager)
     }
 
+    /// Wires up the embedder used to compute vectors for future `add_memory`
+    /// calls. Mirrors `ToolRegistry::register_pre_hook`: optional behavior
+    /// attached after construction rather than threaded through `new`.
+    pub fn set_embedder(&mut self, embedder: Arc<dyn Provider>) {
+        self.embedder = Some(embedder);
+    }
+
+    /// Overrides the delta-log size threshold that triggers automatic compaction.
+    pub fn set_compaction_threshold_bytes(&mut self, bytes: u64) {
+        self.compaction_threshold_bytes = bytes;
+    }
+
+    fn snapshot_path(&self, generation: u64) -> String {
+        format!("{}.snapshot.{}.jsonl", self.base_path, generation)
+    }
+
+    fn read_generation(conn: &Connection) -> Result<u64> {
+        match conn.query_row("SELECT value FROM meta WHERE key = 'generation'", [], |row| row.get::<_, String>(0)) {
+            Ok(value) => Ok(value.parse().unwrap_or(0)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Deletes this manager's leftover `.tmp` files (a crash mid-write to a
+    /// snapshot or the delta log) and any `*.snapshot.<gen>.jsonl` whose
+    /// generation doesn't match `current_generation` (a compaction that
+    /// wrote its snapshot but crashed before or while committing the new
+    /// generation). Scoped to files prefixed with this manager's own
+    /// `base_path` so it never touches unrelated files in a shared directory.
+    fn cleanup_orphans(base_path: &str, current_generation: u64) -> Result<()> {
+        let path = Path::new(base_path);
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).map(Path::to_path_buf).unwrap_or_else(|| Path::new(".").to_path_buf());
+        let stem = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(n) => n,
+                None => continue,
+            };
+            if !name.starts_with(&stem) {
+                continue;
+            }
+
+            if name.ends_with(".tmp") {
+                let _ = fs::remove_file(entry.path());
+                continue;
+            }
+
+            if let Some(generation) = Self::parse_snapshot_generation(name, &stem) {
+                if generation != current_generation {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_snapshot_generation(name: &str, stem: &str) -> Option<u64> {
+        name.strip_prefix(stem)?
+            .strip_prefix(".snapshot.")?
+            .strip_suffix(".jsonl")?
+            .parse()
+            .ok()
+    }
+
     fn setup_db(conn: &mut Connection) -> Result<()> {
         // Main metadata table with explicit INTEGER PRIMARY KEY for reliable rowid
         conn.execute(
@@ -53,7 +254,8 @@ impl MemoryManager {
                 id TEXT NOT NULL UNIQUE,
                 content TEXT NOT NULL,
                 summary TEXT NOT NULL,
-                timestamp TEXT NOT NULL
+                timestamp TEXT NOT NULL,
+                attributes TEXT NOT NULL DEFAULT '{}'
             )",
             [],
         )?;
@@ -83,30 +285,83 @@ impl MemoryManager {
             END;"
         )?;
 
+        // Semantic index: one row per memory with an embedding, kept separate
+        // from `memories` since not every memory has a vector (no embedder
+        // configured, or the log predates embeddings).
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS memory_vectors (
+                id TEXT PRIMARY KEY,
+                dim INTEGER NOT NULL,
+                vector BLOB NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_memories_timestamp ON memories(timestamp)",
+            [],
+        )?;
+
+        // Tracks the current compaction generation (see `compact`), so
+        // `sync_index` knows which snapshot file to load on startup.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )?;
+
         Ok(())
     }
 
+    /// Rebuilds the SQLite index from durable state: the latest snapshot (if
+    /// any), then the delta log replayed on top of it. Every `MemoryOp` is an
+    /// idempotent upsert/delete keyed by id, so replaying the same op twice
+    /// (which can happen across a crash mid-compaction) is harmless.
     fn sync_index(&mut self) -> Result<()> {
-        if !Path::new(&self.log_path).exists() {
-            return Ok(());
+        let generation = Self::read_generation(&self.db_conn)?;
+        if generation > 0 {
+            let snapshot_path = self.snapshot_path(generation);
+            if Path::new(&snapshot_path).exists() {
+                self.replay_ops_file(&snapshot_path)?;
+            }
         }
 
+        if Path::new(&self.log_path).exists() {
+            self.replay_ops_file(&self.log_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn replay_ops_file(&mut self, path: &str) -> Result<()> {
         let tx = self.db_conn.transaction()?;
-        
-        let file = fs::File::open(&self.log_path)?;
+
+        let file = fs::File::open(path)?;
         let reader = io::BufReader::new(file);
 
         for line in reader.lines() {
             let op: MemoryOp = serde_json::from_str(&line?)?;
             match op {
-                MemoryOp::Add { id, content, summary, timestamp } => {
+                MemoryOp::Add { id, content, summary, timestamp, embedding, attributes } => {
                     tx.execute(
-                        "INSERT OR REPLACE INTO memories (id, content, summary, timestamp) VALUES (?, ?, ?, ?)",
-                        params![id.to_string(), content, summary, timestamp.to_rfc3339()],
+                        "INSERT OR REPLACE INTO memories (id, content, summary, timestamp, attributes) VALUES (?, ?, ?, ?, ?)",
+                        params![id.to_string(), content, summary, timestamp.to_rfc3339(), serde_json::to_string(&attributes)?],
                     )?;
+                    if let Some(vector) = embedding {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO memory_vectors (id, dim, vector) VALUES (?, ?, ?)",
+                            params![id.to_string(), vector.len() as i64, vector_to_blob(&vector)],
+                        )?;
+                    }
                 }
                 MemoryOp::Forget { id } => {
                     tx.execute("DELETE FROM memories WHERE id = ?", params![id.to_string()])?;
+                    tx.execute("DELETE FROM memory_vectors WHERE id = ?", params![id.to_string()])?;
+                }
+                MemoryOp::Update { id, content, summary } => {
+                    tx.execute(
+                        "UPDATE memories SET content = ?, summary = ? WHERE id = ?",
+                        params![content, summary, id.to_string()],
+                    )?;
                 }
             }
         }
@@ -114,14 +369,34 @@ impl MemoryManager {
         Ok(())
     }
 
-    pub fn add_memory(&mut self, content: &str, summary: &str) -> Result<Uuid> {
+    pub async fn add_memory(&mut self, content: &str, summary: &str) -> Result<Uuid> {
+        self.add_memory_with_attributes(content, summary, HashMap::new()).await
+    }
+
+    /// Like `add_memory`, but attaches a `tags`/`attributes` map (already
+    /// coerced via `Conversion::parse`) that `search_filtered` can later
+    /// match on.
+    pub async fn add_memory_with_attributes(
+        &mut self,
+        content: &str,
+        summary: &str,
+        attributes: HashMap<String, serde_json::Value>,
+    ) -> Result<Uuid> {
         let id = Uuid::new_v4();
         let timestamp = Utc::now();
+
+        let embedding = match &self.embedder {
+            Some(embedder) => Some(embedder.get_embedding(content).await?),
+            None => None,
+        };
+
         let op = MemoryOp::Add {
             id,
             content: content.to_string(),
             summary: summary.to_string(),
             timestamp,
+            embedding: embedding.clone(),
+            attributes: attributes.clone(),
         };
 
         // Append to log
@@ -129,27 +404,143 @@ impl MemoryManager {
 
         // Update index
         self.db_conn.execute(
-            "INSERT INTO memories (id, content, summary, timestamp) VALUES (?, ?, ?, ?)",
-            params![id.to_string(), content, summary, timestamp.to_rfc3339()],
+            "INSERT INTO memories (id, content, summary, timestamp, attributes) VALUES (?, ?, ?, ?, ?)",
+            params![id.to_string(), content, summary, timestamp.to_rfc3339(), serde_json::to_string(&attributes)?],
         )?;
 
+        if let Some(vector) = embedding {
+            self.db_conn.execute(
+                "INSERT OR REPLACE INTO memory_vectors (id, dim, vector) VALUES (?, ?, ?)",
+                params![id.to_string(), vector.len() as i64, vector_to_blob(&vector)],
+            )?;
+        }
+
+        self.maybe_compact()?;
+
         Ok(id)
     }
 
+    /// Compacts if the delta log has grown past `compaction_threshold_bytes`.
+    fn maybe_compact(&mut self) -> Result<()> {
+        let size = fs::metadata(&self.log_path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.compaction_threshold_bytes {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
     pub fn forget_memory(&mut self, id: Uuid) -> Result<()> {
         let op = MemoryOp::Forget { id };
         self.append_to_log(&op)?;
 
         self.db_conn.execute("DELETE FROM memories WHERE id = ?", params![id.to_string()])?;
+        self.db_conn.execute("DELETE FROM memory_vectors WHERE id = ?", params![id.to_string()])?;
+        Ok(())
+    }
+
+    /// Edits a memory's text in place (no forget+re-add), via the new
+    /// `MemoryOp::Update` log entry.
+    pub fn update_memory(&mut self, id: Uuid, content: &str, summary: &str) -> Result<()> {
+        let op = MemoryOp::Update { id, content: content.to_string(), summary: summary.to_string() };
+        self.append_to_log(&op)?;
+
+        self.db_conn.execute(
+            "UPDATE memories SET content = ?, summary = ? WHERE id = ?",
+            params![content, summary, id.to_string()],
+        )?;
         Ok(())
     }
 
+    /// Appends every op to the log and applies it to the index inside a
+    /// single SQLite transaction, amortizing fsync/commit cost across the
+    /// batch. Unlike `add_memory`, this never computes
+    /// embeddings itself: `Add` ops carry whatever `embedding` the caller
+    /// already set (or `None`), which matters for bulk imports/replays where
+    /// recomputing per item would defeat the point of batching.
+    pub fn apply_batch(&mut self, ops: &[MemoryOp]) -> Result<Vec<Uuid>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)?;
+
+        let tx = self.db_conn.transaction()?;
+        let mut ids = Vec::with_capacity(ops.len());
+
+        for op in ops {
+            writeln!(file, "{}", serde_json::to_string(op)?)?;
+
+            match op {
+                MemoryOp::Add { id, content, summary, timestamp, embedding, attributes } => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO memories (id, content, summary, timestamp, attributes) VALUES (?, ?, ?, ?, ?)",
+                        params![id.to_string(), content, summary, timestamp.to_rfc3339(), serde_json::to_string(attributes)?],
+                    )?;
+                    if let Some(vector) = embedding {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO memory_vectors (id, dim, vector) VALUES (?, ?, ?)",
+                            params![id.to_string(), vector.len() as i64, vector_to_blob(vector)],
+                        )?;
+                    }
+                    ids.push(*id);
+                }
+                MemoryOp::Forget { id } => {
+                    tx.execute("DELETE FROM memories WHERE id = ?", params![id.to_string()])?;
+                    tx.execute("DELETE FROM memory_vectors WHERE id = ?", params![id.to_string()])?;
+                    ids.push(*id);
+                }
+                MemoryOp::Update { id, content, summary } => {
+                    tx.execute(
+                        "UPDATE memories SET content = ?, summary = ? WHERE id = ?",
+                        params![content, summary, id.to_string()],
+                    )?;
+                    ids.push(*id);
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    /// Memories with `start <= timestamp <= end`, oldest first. Lets callers
+    /// replay or expire memories by time window instead of only by FTS match.
+    pub fn range_by_time(&self, start: DateTime<Utc>, end: DateTime<Utc>, limit: usize) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.db_conn.prepare(
+            "SELECT content, summary FROM memories WHERE timestamp >= ? AND timestamp <= ? ORDER BY timestamp ASC LIMIT ?"
+        )?;
+        let rows = stmt.query_map(params![start.to_rfc3339(), end.to_rfc3339(), limit], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// The `limit` most recently added memories, newest first.
+    pub fn list_recent(&self, limit: usize) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.db_conn.prepare(
+            "SELECT content, summary FROM memories ORDER BY timestamp DESC LIMIT ?"
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
     fn append_to_log(&self, op: &MemoryOp) -> Result<()> {
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&self.log_path)?;
-        
+
         let json = serde_json::to_string(op)?;
         writeln!(file, "{}", json)?;
         Ok(())
@@ -167,7 +558,7 @@ impl MemoryManager {
         let mut stmt = self.db_conn.prepare(
             "SELECT content, summary FROM memories_fts WHERE memories_fts MATCH ? LIMIT ?"
         )?;
-        
+
         let rows = stmt.query_map(params![sanitized_query, limit], |row| {
             Ok((row.get(0)?, row.get(1)?))
         })?;
@@ -179,30 +570,219 @@ impl MemoryManager {
         Ok(results)
     }
 
+    /// Combines an FTS5 `MATCH` with equality filters on typed attributes,
+    /// e.g. `search_filtered("standup", &[("priority".into(), Conversion::Integer, "3".into())], 5)`
+    /// to only return memories tagged `priority = 3`. Each filter value is
+    /// coerced through its `Conversion` before comparison, so `"3"` matches
+    /// the JSON integer `3` rather than the literal string.
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        filters: &[(String, Conversion, String)],
+        limit: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let sanitized_query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('\"', "")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut sql = String::from(
+            "SELECT m.content, m.summary FROM memories_fts f JOIN memories m ON m.rowid = f.rowid \
+             WHERE memories_fts MATCH ?"
+        );
+        let mut sql_params: Vec<rusqlite::types::Value> = vec![rusqlite::types::Value::Text(sanitized_query)];
+
+        for (key, conversion, raw) in filters {
+            validate_attribute_key(key)?;
+            sql.push_str(&format!(" AND json_extract(m.attributes, '$.{}') = ?", key));
+            sql_params.push(attribute_to_sql(conversion.parse(raw)?));
+        }
+
+        sql.push_str(" LIMIT ?");
+        sql_params.push(rusqlite::types::Value::Integer(limit as i64));
+
+        let mut stmt = self.db_conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(sql_params.iter()), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Ranked memory ids from the FTS5 lexical query, most relevant first.
+    /// Uses the same sanitized `MATCH` as `search`; feeds `search_hybrid`'s RRF fusion.
+    fn search_fts_ids(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let sanitized_query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('\"', "")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut stmt = self.db_conn.prepare(
+            "SELECT m.id FROM memories_fts f JOIN memories m ON m.rowid = f.rowid
+             WHERE memories_fts MATCH ? ORDER BY f.rank LIMIT ?"
+        )?;
+
+        let rows = stmt.query_map(params![sanitized_query, limit], |row| row.get::<_, String>(0))?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row?);
+        }
+        Ok(ids)
+    }
+
+    /// Ranked memory ids from the vector cosine-similarity query, most similar first.
+    fn search_semantic_ids(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<String>> {
+        let mut stmt = self.db_conn.prepare("SELECT id, vector FROM memory_vectors")?;
+        let rows = stmt.query_map([], |row| {
+            let id: String = row.get(0)?;
+            let vector: Vec<u8> = row.get(1)?;
+            Ok((id, blob_to_vector(&vector)))
+        })?;
+
+        let mut scored = Vec::new();
+        for row in rows {
+            let (id, vector) = row?;
+            scored.push((cosine_similarity(query_embedding, &vector), id));
+        }
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().take(limit).map(|(_, id)| id).collect())
+    }
+
+    /// Fuses `search`'s lexical ranking with `search_semantic`'s vector
+    /// ranking via Reciprocal Rank Fusion: each id scores `sum(1 / (k + rank))`
+    /// over whichever of the two ranked lists it appears in (1-based rank; ids
+    /// absent from a list simply contribute nothing). RRF needs no score
+    /// normalization between the two heterogeneous rankers and still works
+    /// when one of them returns few or no hits.
+    pub fn search_hybrid(&self, query: &str, query_embedding: &[f32], limit: usize) -> Result<Vec<(String, String)>> {
+        const K: f64 = 60.0;
+
+        let fts_ids = self.search_fts_ids(query, limit.max(1) * 4)?;
+        let semantic_ids = self.search_semantic_ids(query_embedding, limit.max(1) * 4)?;
+
+        let mut fused: HashMap<String, f64> = HashMap::new();
+        for (rank, id) in fts_ids.into_iter().enumerate() {
+            *fused.entry(id).or_insert(0.0) += 1.0 / (K + (rank + 1) as f64);
+        }
+        for (rank, id) in semantic_ids.into_iter().enumerate() {
+            *fused.entry(id).or_insert(0.0) += 1.0 / (K + (rank + 1) as f64);
+        }
+
+        let mut ranked: Vec<(String, f64)> = fused.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut results = Vec::new();
+        for (id, _) in ranked.into_iter().take(limit) {
+            let row = self.db_conn.query_row(
+                "SELECT content, summary FROM memories WHERE id = ?",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            );
+            if let Ok((content, summary)) = row {
+                results.push((content, summary));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Ranks memories by cosine similarity of their stored embedding against
+    /// `query_embedding`, for recall that's robust to vocabulary mismatch
+    /// (paraphrases, synonyms) that defeats `search`'s FTS5 matching.
+    pub fn search_semantic(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.db_conn.prepare(
+            "SELECT m.content, m.summary, v.vector
+             FROM memory_vectors v JOIN memories m ON m.id = v.id"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let content: String = row.get(0)?;
+            let summary: String = row.get(1)?;
+            let vector: Vec<u8> = row.get(2)?;
+            Ok((content, summary, blob_to_vector(&vector)))
+        })?;
+
+        let mut scored = Vec::new();
+        for row in rows {
+            let (content, summary, vector) = row?;
+            let score = cosine_similarity(query_embedding, &vector);
+            scored.push((score, content, summary));
+        }
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().take(limit).map(|(_, content, summary)| (content, summary)).collect())
+    }
+
+    /// Rotates the delta log into a fresh, generation-stamped snapshot so
+    /// replay time on startup stays bounded by recent activity rather than
+    /// the full memory history. Ordered for crash safety:
+    ///
+    /// 1. Write the new snapshot and rename it into place (atomic).
+    /// 2. Commit the new generation number.
+    /// 3. Truncate the delta log (atomic rename over an empty temp file).
+    ///
+    /// A crash between any of these steps leaves the pair in a state that
+    /// `sync_index` + `cleanup_orphans` can recover deterministically: a
+    /// snapshot written but not yet committed (step 1 done, not 2) is an
+    /// orphan of a generation nothing points to, so it's discarded on next
+    /// startup; a commit not yet followed by truncation (step 2 done, not 3)
+    /// just means the delta log's (now-redundant) ops get replayed again on
+    /// top of the snapshot, which is harmless since every op is idempotent.
     pub fn compact(&mut self) -> Result<()> {
-        // Read all current memories from the DB (the source of truth for active state)
-        let mut stmt = self.db_conn.prepare("SELECT id, content, summary, timestamp FROM memories")?;
+        let next_generation = Self::read_generation(&self.db_conn)? + 1;
+
+        let snapshot_path = self.snapshot_path(next_generation);
+        let temp_snapshot_path = format!("{}.tmp", snapshot_path);
+        self.write_snapshot(&temp_snapshot_path)?;
+        fs::rename(&temp_snapshot_path, &snapshot_path)?;
+
+        self.db_conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('generation', ?)",
+            params![next_generation.to_string()],
+        )?;
+
+        let temp_log_path = format!("{}.tmp", self.log_path);
+        fs::File::create(&temp_log_path)?;
+        fs::rename(temp_log_path, &self.log_path)?;
+
+        // Reap snapshots from generations this compaction just superseded,
+        // same as the startup-only cleanup, so a long-running process
+        // doesn't accumulate one snapshot file per compaction forever.
+        Self::cleanup_orphans(&self.base_path, next_generation)?;
+
+        Ok(())
+    }
+
+    /// Writes every currently-active memory (the DB is the source of truth)
+    /// to `path` as a stream of `MemoryOp::Add` lines, the same format as the
+    /// delta log so `replay_ops_file` can load either.
+    fn write_snapshot(&self, path: &str) -> Result<()> {
+        let mut stmt = self.db_conn.prepare(
+            "SELECT m.id, m.content, m.summary, m.timestamp, m.attributes, v.vector
+             FROM memories m LEFT JOIN memory_vectors v ON v.id = m.id"
+        )?;
         let rows = stmt.query_map([], |row| {
+            let vector: Option<Vec<u8>> = row.get(5)?;
             Ok(MemoryOp::Add {
                 id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap(),
                 content: row.get(1)?,
                 summary: row.get(2)?,
                 timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?).unwrap().with_timezone(&Utc),
+                attributes: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                embedding: vector.map(|blob| blob_to_vector(&blob)),
             })
         })?;
 
-        let temp_log_path = format!("{}.tmp", self.log_path);
-        let mut file = fs::File::create(&temp_log_path)?;
-
+        let mut file = fs::File::create(path)?;
         for row in rows {
             let op = row?;
-            let json = serde_json::to_string(&op)?;
-            writeln!(file, "{}", json)?;
+            writeln!(file, "{}", serde_json::to_string(&op)?)?;
         }
-
-        // Atomically replace the log
-        fs::rename(temp_log_path, &self.log_path)?;
-
         Ok(())
     }
 }
@@ -0,0 +1,164 @@
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex as StdMutex};
+use uuid::Uuid;
+
+/// Wraps whatever the process was actually spawned with, just enough to
+/// kill it and poll its exit status. Kept as a plain enum rather than a
+/// trait object since there are only ever these two shapes.
+enum ChildKiller {
+    Pty(Box<dyn portable_pty::Child + Send + Sync>),
+    Plain(std::process::Child),
+}
+
+impl ChildKiller {
+    fn kill(&mut self) -> anyhow::Result<()> {
+        match self {
+            ChildKiller::Pty(child) => Ok(child.kill()?),
+            ChildKiller::Plain(child) => Ok(child.kill()?),
+        }
+    }
+
+    fn exit_code(&mut self) -> anyhow::Result<Option<i32>> {
+        match self {
+            ChildKiller::Pty(child) => Ok(child.try_wait()?.map(|status| status.exit_code() as i32)),
+            ChildKiller::Plain(child) => Ok(child.try_wait()?.map(|status| status.code().unwrap_or(-1))),
+        }
+    }
+}
+
+/// A spawned process backed by a PTY when the platform supports one (so
+/// interactive programs that check `isatty()` behave normally), falling
+/// back to plain piped stdio otherwise. A background thread continuously
+/// drains stdout/stderr into `output`, so `read_output` is just "whatever
+/// arrived since the cursor" rather than a one-shot blocking read.
+pub struct ProcessHandle {
+    pub id: Uuid,
+    pub command: String,
+    output: Arc<StdMutex<Vec<u8>>>,
+    read_cursor: StdMutex<usize>,
+    writer: StdMutex<Box<dyn Write + Send>>,
+    child: StdMutex<ChildKiller>,
+}
+
+impl ProcessHandle {
+    pub fn write_stdin(&self, data: &[u8]) -> anyhow::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(data)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns whatever output has arrived since the last call.
+    pub fn read_output(&self) -> Vec<u8> {
+        let output = self.output.lock().unwrap();
+        let mut cursor = self.read_cursor.lock().unwrap();
+        let chunk = output[*cursor..].to_vec();
+        *cursor = output.len();
+        chunk
+    }
+
+    pub fn kill(&self) -> anyhow::Result<()> {
+        self.child.lock().unwrap().kill()
+    }
+
+    /// `Some(exit_code)` once the process has exited, `None` while it's still running.
+    pub fn exit_code(&self) -> anyhow::Result<Option<i32>> {
+        self.child.lock().unwrap().exit_code()
+    }
+}
+
+fn spawn_reader_thread(mut reader: impl Read + Send + 'static, output: Arc<StdMutex<Vec<u8>>>) {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.lock().unwrap().extend_from_slice(&buf[..n]),
+            }
+        }
+    });
+}
+
+fn spawn_pty(
+    command: &str,
+    args: &[String],
+    output: Arc<StdMutex<Vec<u8>>>,
+) -> anyhow::Result<(Box<dyn Write + Send>, ChildKiller)> {
+    let pty_system = portable_pty::native_pty_system();
+    let pair = pty_system.openpty(portable_pty::PtySize {
+        rows: 24,
+        cols: 80,
+        pixel_width: 0,
+        pixel_height: 0,
+    })?;
+
+    let mut cmd = portable_pty::CommandBuilder::new(command);
+    cmd.args(args);
+    let child = pair.slave.spawn_command(cmd)?;
+    drop(pair.slave);
+
+    let reader = pair.master.try_clone_reader()?;
+    let writer = pair.master.take_writer()?;
+    let master = pair.master;
+
+    std::thread::spawn(move || {
+        let _master = master; // keep the PTY open for the life of the reader thread
+        let mut reader = reader;
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.lock().unwrap().extend_from_slice(&buf[..n]),
+            }
+        }
+    });
+
+    Ok((writer, ChildKiller::Pty(child)))
+}
+
+fn spawn_piped(
+    command: &str,
+    args: &[String],
+    output: Arc<StdMutex<Vec<u8>>>,
+) -> anyhow::Result<(Box<dyn Write + Send>, ChildKiller)> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("Failed to open stdin for {}", command))?;
+    let stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("Failed to open stdout for {}", command))?;
+    let stderr = child.stderr.take().ok_or_else(|| anyhow::anyhow!("Failed to open stderr for {}", command))?;
+
+    spawn_reader_thread(stdout, output.clone());
+    spawn_reader_thread(stderr, output);
+
+    Ok((Box::new(stdin), ChildKiller::Plain(child)))
+}
+
+/// Spawns `command` as a persistent process, preferring a PTY and falling
+/// back to plain piped stdio if the platform can't open one.
+pub fn spawn(command: &str, args: &[String]) -> anyhow::Result<ProcessHandle> {
+    let output = Arc::new(StdMutex::new(Vec::new()));
+
+    let (writer, child) = match spawn_pty(command, args, output.clone()) {
+        Ok(backend) => backend,
+        Err(e) => {
+            tracing::warn!("PTY spawn failed for `{}` ({}), falling back to piped stdio", command, e);
+            spawn_piped(command, args, output.clone())?
+        }
+    };
+
+    Ok(ProcessHandle {
+        id: Uuid::new_v4(),
+        command: command.to_string(),
+        output,
+        read_cursor: StdMutex::new(0),
+        writer: StdMutex::new(writer),
+        child: StdMutex::new(child),
+    })
+}
@@ -0,0 +1,67 @@
+//! Wire protocol exchanged between a coordinator `Agent` and remote worker
+//! agents. Messages are framed as newline-delimited JSON so they can travel over
+//! an in-process `tokio::sync::mpsc` channel, a TCP stream, or a unix socket.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use uuid::Uuid;
+
+/// A unit of work dispatched from the coordinator to a worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestedTask {
+    pub id: Uuid,
+    pub description: String,
+    /// Optional preamble (relevant history, prior results) prepended to the prompt.
+    pub context: Option<String>,
+    /// The `artifacts/<id>/` directory `Coordinator::dispatch` reserved for
+    /// this task, if any, so the worker knows where to save tool outputs and
+    /// written files it should report back in `TaskUpdate.artifacts`.
+    #[serde(default)]
+    pub artifact_dir: Option<PathBuf>,
+}
+
+/// Lifecycle state reported by a worker for a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A progress report sent from a worker back to the coordinator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskUpdate {
+    pub id: Uuid,
+    pub state: TaskState,
+    pub output: Option<String>,
+    #[serde(default)]
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// Writes a single value as a newline-terminated JSON frame.
+pub async fn write_frame<W, T>(writer: &mut W, value: &T) -> anyhow::Result<()>
+where
+    W: AsyncWriteExt + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads a single newline-delimited JSON frame, returning `None` at end of stream.
+pub async fn read_frame<R, T>(reader: &mut BufReader<R>) -> anyhow::Result<Option<T>>
+where
+    R: AsyncBufReadExt + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    let mut line = String::new();
+    let n = reader.read_line(&mut line).await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(line.trim_end())?))
+}
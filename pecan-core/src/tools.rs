@@ -1,9 +1,12 @@
 use crate::{Tool, TaskStack};
+use crate::process::ProcessHandle;
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fs;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
 pub struct ReadFile;
 
@@ -161,3 +164,129 @@ impl Tool for Shell {
         }))
     }
 }
+
+pub struct StartProcess {
+    pub processes: Arc<Mutex<HashMap<Uuid, ProcessHandle>>>,
+}
+
+#[async_trait]
+impl Tool for StartProcess {
+    fn name(&self) -> &str { "start_process" }
+    fn description(&self) -> &str { "Starts a persistent process (PTY-backed when available) for interactive or long-running work, returning its process id." }
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "description": "The command to execute." },
+                "args": { "type": "array", "items": { "type": "string" }, "description": "Arguments for the command." }
+            },
+            "required": ["command"]
+        })
+    }
+    async fn call(&self, arguments: Value) -> anyhow::Result<Value> {
+        let command = arguments["command"].as_str().ok_or_else(|| anyhow::anyhow!("Missing command"))?;
+        let args: Vec<String> = arguments["args"].as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+
+        let handle = crate::process::spawn(command, &args)?;
+        let id = handle.id;
+
+        let mut processes = self.processes.lock().await;
+        processes.insert(id, handle);
+
+        Ok(json!({ "process_id": id.to_string() }))
+    }
+}
+
+pub struct WriteStdin {
+    pub processes: Arc<Mutex<HashMap<Uuid, ProcessHandle>>>,
+}
+
+#[async_trait]
+impl Tool for WriteStdin {
+    fn name(&self) -> &str { "write_stdin" }
+    fn description(&self) -> &str { "Writes data to a process started with start_process." }
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "process_id": { "type": "string", "description": "The process id returned by start_process." },
+                "data": { "type": "string", "description": "Text to write to the process's stdin." }
+            },
+            "required": ["process_id", "data"]
+        })
+    }
+    async fn call(&self, arguments: Value) -> anyhow::Result<Value> {
+        let id = Uuid::parse_str(arguments["process_id"].as_str().ok_or_else(|| anyhow::anyhow!("Missing process_id"))?)?;
+        let data = arguments["data"].as_str().ok_or_else(|| anyhow::anyhow!("Missing data"))?;
+
+        let processes = self.processes.lock().await;
+        let handle = processes.get(&id).ok_or_else(|| anyhow::anyhow!("No such process: {}", id))?;
+        handle.write_stdin(data.as_bytes())?;
+
+        Ok(json!({ "status": "written" }))
+    }
+}
+
+pub struct ReadOutput {
+    pub processes: Arc<Mutex<HashMap<Uuid, ProcessHandle>>>,
+}
+
+#[async_trait]
+impl Tool for ReadOutput {
+    fn name(&self) -> &str { "read_output" }
+    fn description(&self) -> &str { "Reads whatever output a process has produced since the last read_output call." }
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "process_id": { "type": "string", "description": "The process id returned by start_process." }
+            },
+            "required": ["process_id"]
+        })
+    }
+    async fn call(&self, arguments: Value) -> anyhow::Result<Value> {
+        let id = Uuid::parse_str(arguments["process_id"].as_str().ok_or_else(|| anyhow::anyhow!("Missing process_id"))?)?;
+
+        let processes = self.processes.lock().await;
+        let handle = processes.get(&id).ok_or_else(|| anyhow::anyhow!("No such process: {}", id))?;
+        let output = handle.read_output();
+        let exit_code = handle.exit_code()?;
+
+        Ok(json!({
+            "output": String::from_utf8_lossy(&output),
+            "exit_code": exit_code,
+        }))
+    }
+}
+
+pub struct KillProcess {
+    pub processes: Arc<Mutex<HashMap<Uuid, ProcessHandle>>>,
+}
+
+#[async_trait]
+impl Tool for KillProcess {
+    fn name(&self) -> &str { "kill_process" }
+    fn description(&self) -> &str { "Kills a process started with start_process." }
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "process_id": { "type": "string", "description": "The process id returned by start_process." }
+            },
+            "required": ["process_id"]
+        })
+    }
+    async fn call(&self, arguments: Value) -> anyhow::Result<Value> {
+        let id = Uuid::parse_str(arguments["process_id"].as_str().ok_or_else(|| anyhow::anyhow!("Missing process_id"))?)?;
+
+        let mut processes = self.processes.lock().await;
+        let handle = processes.remove(&id).ok_or_else(|| anyhow::anyhow!("No such process: {}", id))?;
+        handle.kill()?;
+
+        Ok(json!({ "status": "killed" }))
+    }
+}
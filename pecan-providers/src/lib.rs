@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+use async_stream::try_stream;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,9 +46,24 @@ pub struct ChatCompletionResponse {
     pub tool_calls: Option<Vec<ToolCall>>,
 }
 
+/// One incremental event from a streaming chat completion: either a fragment
+/// of assistant text, or a fully-assembled tool call (buffered across
+/// however many SSE events it took to arrive).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatCompletionDelta {
+    pub content: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+pub type ChatStream = std::pin::Pin<Box<dyn futures_core::Stream<Item = anyhow::Result<ChatCompletionDelta>> + Send>>;
+
 #[async_trait]
 pub trait Provider: Send + Sync {
     async fn chat_completion(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatCompletionResponse>;
+    /// Streaming counterpart to `chat_completion`, used by callers (e.g. the
+    /// axum chat handler) that want to forward tokens to the client as they
+    /// arrive instead of buffering the whole reply.
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatStream>;
     async fn get_embedding(&self, text: &str) -> anyhow::Result<Vec<f32>>;
     async fn tokenize(&self, text: &str) -> anyhow::Result<Vec<u32>>;
     async fn detokenize(&self, tokens: Vec<u32>) -> anyhow::Result<String>;
@@ -54,6 +71,121 @@ pub trait Provider: Send + Sync {
     async fn health_check(&self) -> anyhow::Result<bool>;
 }
 
+/// Accumulates one `choices[0].delta.tool_calls[i]` entry across SSE events
+/// until its `index` changes or the stream ends, at which point the
+/// buffered `function.arguments` fragments are parsed as JSON and the call
+/// is considered complete.
+#[derive(Default)]
+struct PartialToolCall {
+    index: usize,
+    id: String,
+    r#type: String,
+    function_name: String,
+    function_arguments: String,
+}
+
+impl PartialToolCall {
+    fn finalize(&self) -> anyhow::Result<ToolCall> {
+        // A model streaming a no-param tool call legitimately emits an empty
+        // (or whitespace-only) arguments string rather than "{}", so treat
+        // that case as an empty object instead of rejecting it below.
+        let arguments = if self.function_arguments.trim().is_empty() {
+            "{}".to_string()
+        } else {
+            self.function_arguments.clone()
+        };
+
+        serde_json::from_str::<serde_json::Value>(&arguments).map_err(|e| {
+            anyhow::anyhow!(
+                "Streamed tool call arguments are not valid JSON: {} ({:?})",
+                e,
+                arguments
+            )
+        })?;
+        Ok(ToolCall {
+            id: self.id.clone(),
+            r#type: self.r#type.clone(),
+            function: ToolFunction {
+                name: self.function_name.clone(),
+                arguments,
+            },
+        })
+    }
+}
+
+/// Parses an OpenAI-compatible `text/event-stream` body into a stream of
+/// `ChatCompletionDelta`s. Shared by `LlamaCppProvider` and `OpenAiProvider`
+/// since both backends speak the same `/v1/chat/completions` SSE shape.
+fn parse_openai_style_sse(response: reqwest::Response) -> ChatStream {
+    Box::pin(try_stream! {
+        let mut byte_stream = response.bytes_stream();
+        let mut buffer = String::new();
+        let mut current: Option<PartialToolCall> = None;
+
+        while let Some(chunk) = byte_stream.next().await {
+            buffer.push_str(&String::from_utf8_lossy(&chunk?));
+
+            while let Some(newline) = buffer.find('\n') {
+                let line = buffer[..newline].trim_end_matches('\r').to_string();
+                buffer.drain(..=newline);
+
+                let data = match line.strip_prefix("data:") {
+                    Some(d) => d.trim(),
+                    None => continue,
+                };
+
+                if data == "[DONE]" {
+                    if let Some(partial) = current.take() {
+                        yield ChatCompletionDelta { content: None, tool_calls: Some(vec![partial.finalize()?]) };
+                    }
+                    return;
+                }
+
+                let event: serde_json::Value = match serde_json::from_str(data) {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+
+                let delta = &event["choices"][0]["delta"];
+
+                if let Some(content) = delta.get("content").and_then(|c| c.as_str()) {
+                    yield ChatCompletionDelta { content: Some(content.to_string()), tool_calls: None };
+                }
+
+                if let Some(calls) = delta.get("tool_calls").and_then(|c| c.as_array()) {
+                    for call in calls {
+                        let index = call.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+
+                        if current.as_ref().is_some_and(|p| p.index != index) {
+                            let finished = current.take().unwrap();
+                            yield ChatCompletionDelta { content: None, tool_calls: Some(vec![finished.finalize()?]) };
+                        }
+
+                        let partial = current.get_or_insert_with(|| PartialToolCall { index, ..Default::default() });
+
+                        if let Some(id) = call.get("id").and_then(|v| v.as_str()) {
+                            partial.id = id.to_string();
+                        }
+                        if let Some(t) = call.get("type").and_then(|v| v.as_str()) {
+                            partial.r#type = t.to_string();
+                        }
+                        if let Some(name) = call.get("function").and_then(|f| f.get("name")).and_then(|n| n.as_str()) {
+                            partial.function_name.push_str(name);
+                        }
+                        if let Some(args) = call.get("function").and_then(|f| f.get("arguments")).and_then(|a| a.as_str()) {
+                            partial.function_arguments.push_str(args);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(partial) = current.take() {
+            yield ChatCompletionDelta { content: None, tool_calls: Some(vec![partial.finalize()?]) };
+        }
+    })
+}
+
 pub struct MockProvider;
 
 #[async_trait]
@@ -64,6 +196,13 @@ impl Provider for MockProvider {
             tool_calls: None,
         })
     }
+    async fn chat_completion_stream(&self, _request: ChatCompletionRequest) -> anyhow::Result<ChatStream> {
+        let delta = ChatCompletionDelta {
+            content: Some("Mock response".to_string()),
+            tool_calls: None,
+        };
+        Ok(Box::pin(futures_util::stream::iter(vec![Ok(delta)])))
+    }
     async fn get_embedding(&self, _text: &str) -> anyhow::Result<Vec<f32>> {
         Ok(vec![0.0; 384])
     }
@@ -138,6 +277,22 @@ impl Provider for LlamaCppProvider {
         Ok(ChatCompletionResponse { content, tool_calls })
     }
 
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatStream> {
+        let client = reqwest::Client::new();
+        let base_url = self.url.trim_end_matches('/');
+        let endpoint = if base_url.ends_with("/v1") {
+            format!("{}/chat/completions", base_url)
+        } else {
+            format!("{}/v1/chat/completions", base_url)
+        };
+
+        let mut request_json = serde_json::to_value(&request)?;
+        request_json["stream"] = serde_json::Value::Bool(true);
+
+        let response = client.post(endpoint).json(&request_json).send().await?;
+        Ok(parse_openai_style_sse(response))
+    }
+
     async fn get_embedding(&self, text: &str) -> anyhow::Result<Vec<f32>> {
         let client = reqwest::Client::new();
         let base_url = self.url.trim_end_matches('/');
@@ -297,6 +452,29 @@ impl Provider for OpenAiProvider {
         Ok(ChatCompletionResponse { content, tool_calls })
     }
 
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatStream> {
+        let client = reqwest::Client::new();
+
+        let mut request_json = serde_json::to_value(&request)?;
+        request_json["model"] = serde_json::Value::String(self.model_id.clone());
+        request_json["stream"] = serde_json::Value::Bool(true);
+
+        let base_url = self.url.trim_end_matches('/');
+        let endpoint = if base_url.ends_with("/v1") {
+            format!("{}/chat/completions", base_url)
+        } else {
+            format!("{}/v1/chat/completions", base_url)
+        };
+
+        let mut rb = client.post(endpoint);
+        if let Some(key) = &self.api_key {
+            rb = rb.header("Authorization", format!("Bearer {}", key));
+        }
+
+        let response = rb.json(&request_json).send().await?;
+        Ok(parse_openai_style_sse(response))
+    }
+
     async fn get_embedding(&self, text: &str) -> anyhow::Result<Vec<f32>> {
         let client = reqwest::Client::new();
         let base_url = self.url.trim_end_matches('/');
@@ -413,3 +591,327 @@ impl Provider for OpenAiProvider {
         Ok(response.status().is_success())
     }
 }
+
+/// Application-default-credentials service account key, as written by
+/// `gcloud auth application-default login` or downloaded from the GCP console.
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: std::time::SystemTime,
+}
+
+/// Tokens are refreshed once less than this much of their lifetime remains,
+/// so an in-flight request never races a token that expires mid-call.
+const TOKEN_REFRESH_SKEW: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub struct VertexAiProvider {
+    pub project_id: String,
+    pub location: String,
+    pub model: String,
+    pub credentials_path: Option<String>,
+    token_cache: tokio::sync::Mutex<Option<CachedToken>>,
+}
+
+impl VertexAiProvider {
+    pub fn new(project_id: String, location: String, model: String, credentials_path: Option<String>) -> Self {
+        Self {
+            project_id,
+            location,
+            model,
+            credentials_path,
+            token_cache: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Returns a cached ADC access token, transparently fetching and
+    /// re-caching a fresh one once the cached token is within
+    /// `TOKEN_REFRESH_SKEW` of expiry.
+    async fn access_token(&self) -> anyhow::Result<String> {
+        {
+            let cache = self.token_cache.lock().await;
+            if let Some(cached) = cache.as_ref() {
+                if cached.expires_at > std::time::SystemTime::now() + TOKEN_REFRESH_SKEW {
+                    return Ok(cached.access_token.clone());
+                }
+            }
+        }
+
+        let (access_token, expires_in) = self.fetch_adc_token().await?;
+        let mut cache = self.token_cache.lock().await;
+        let expires_at = std::time::SystemTime::now() + std::time::Duration::from_secs(expires_in);
+        *cache = Some(CachedToken { access_token: access_token.clone(), expires_at });
+        Ok(access_token)
+    }
+
+    /// Exchanges the configured ADC service account key for an access token
+    /// via a signed JWT assertion, per Google's OAuth2 server-to-server flow.
+    async fn fetch_adc_token(&self) -> anyhow::Result<(String, u64)> {
+        let path = self
+            .credentials_path
+            .clone()
+            .or_else(|| std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok())
+            .ok_or_else(|| anyhow::anyhow!("No ADC credentials configured (set credentials_path or GOOGLE_APPLICATION_CREDENTIALS)"))?;
+
+        let key_json = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read ADC credentials file {}: {}", path, e))?;
+        let key: ServiceAccountKey = serde_json::from_str(&key_json)?;
+
+        let now = jsonwebtoken::get_current_timestamp();
+        let claims = serde_json::json!({
+            "iss": key.client_email,
+            "scope": "https://www.googleapis.com/auth/cloud-platform",
+            "aud": key.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+
+        let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())?;
+        let assertion = jsonwebtoken::encode(&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256), &claims, &encoding_key)?;
+
+        let client = reqwest::Client::new();
+        let response: serde_json::Value = client
+            .post(&key.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let access_token = response["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("ADC token response missing access_token: {}", response))?
+            .to_string();
+        let expires_in = response["expires_in"].as_u64().unwrap_or(3600);
+
+        Ok((access_token, expires_in))
+    }
+
+    fn endpoint(&self, method: &str) -> String {
+        format!(
+            "https://{location}-aiplatform.googleapis.com/v1/projects/{project}/locations/{location}/publishers/google/models/{model}:{method}",
+            location = self.location,
+            project = self.project_id,
+            model = self.model,
+        )
+    }
+
+    /// Splits our `Message`/`Role` history into Vertex's `system_instruction`
+    /// (from any `Role::System` messages) and `contents` list.
+    fn to_vertex_request(messages: &[Message]) -> serde_json::Value {
+        let mut system_parts = Vec::new();
+        let mut contents = Vec::new();
+
+        for message in messages {
+            let text = message.content.clone().unwrap_or_default();
+            match message.role {
+                Role::System => {
+                    system_parts.push(serde_json::json!({ "text": text }));
+                }
+                Role::User | Role::Tool => {
+                    contents.push(serde_json::json!({ "role": "user", "parts": [{ "text": text }] }));
+                }
+                Role::Assistant => {
+                    contents.push(serde_json::json!({ "role": "model", "parts": [{ "text": text }] }));
+                }
+            }
+        }
+
+        let mut body = serde_json::json!({ "contents": contents });
+        if !system_parts.is_empty() {
+            body["system_instruction"] = serde_json::json!({ "parts": system_parts });
+        }
+        body
+    }
+}
+
+#[async_trait]
+impl Provider for VertexAiProvider {
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatCompletionResponse> {
+        let token = self.access_token().await?;
+        let body = Self::to_vertex_request(&request.messages);
+
+        let client = reqwest::Client::new();
+        let response_json = client
+            .post(self.endpoint("generateContent"))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        if let Some(error) = response_json.get("error") {
+            anyhow::bail!("Vertex AI Error: {}", error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error"));
+        }
+
+        let content = response_json["candidates"][0]["content"]["parts"]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                    .collect::<String>()
+            })
+            .filter(|s| !s.is_empty());
+
+        Ok(ChatCompletionResponse { content, tool_calls: None })
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatStream> {
+        let token = self.access_token().await?;
+        let body = Self::to_vertex_request(&request.messages);
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(self.endpoint("streamGenerateContent"))
+            .query(&[("alt", "sse")])
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await?;
+
+        Ok(parse_vertex_sse(response))
+    }
+
+    async fn get_embedding(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        let token = self.access_token().await?;
+        let endpoint = format!(
+            "https://{location}-aiplatform.googleapis.com/v1/projects/{project}/locations/{location}/publishers/google/models/textembedding-gecko:predict",
+            location = self.location,
+            project = self.project_id,
+        );
+
+        let client = reqwest::Client::new();
+        let response_json = client
+            .post(endpoint)
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "instances": [{ "content": text }] }))
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        let embedding = response_json["predictions"][0]["embeddings"]["values"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Invalid Vertex AI embedding response: {}", response_json))?
+            .iter()
+            .filter_map(|v| v.as_f64().map(|f| f as f32))
+            .collect();
+
+        Ok(embedding)
+    }
+
+    async fn tokenize(&self, _text: &str) -> anyhow::Result<Vec<u32>> {
+        anyhow::bail!("VertexAiProvider does not support tokenize")
+    }
+
+    async fn detokenize(&self, _tokens: Vec<u32>) -> anyhow::Result<String> {
+        anyhow::bail!("VertexAiProvider does not support detokenize")
+    }
+
+    async fn list_models(&self) -> anyhow::Result<Vec<String>> {
+        Ok(vec![self.model.clone()])
+    }
+
+    async fn health_check(&self) -> anyhow::Result<bool> {
+        Ok(self.access_token().await.is_ok())
+    }
+}
+
+/// Parses a Vertex AI `streamGenerateContent?alt=sse` body into a stream of
+/// `ChatCompletionDelta`s. Vertex's event shape differs from the
+/// OpenAI-compatible one (`candidates[0].content.parts` rather than
+/// `choices[0].delta`), so this doesn't share `parse_openai_style_sse`.
+fn parse_vertex_sse(response: reqwest::Response) -> ChatStream {
+    Box::pin(try_stream! {
+        let mut byte_stream = response.bytes_stream();
+        let mut buffer = String::new();
+
+        while let Some(chunk) = byte_stream.next().await {
+            buffer.push_str(&String::from_utf8_lossy(&chunk?));
+
+            while let Some(newline) = buffer.find('\n') {
+                let line = buffer[..newline].trim_end_matches('\r').to_string();
+                buffer.drain(..=newline);
+
+                let data = match line.strip_prefix("data:") {
+                    Some(d) => d.trim(),
+                    None => continue,
+                };
+                if data.is_empty() {
+                    continue;
+                }
+
+                let event: serde_json::Value = match serde_json::from_str(data) {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+
+                if let Some(parts) = event["candidates"][0]["content"]["parts"].as_array() {
+                    let text: String = parts.iter().filter_map(|p| p.get("text").and_then(|t| t.as_str())).collect();
+                    if !text.is_empty() {
+                        yield ChatCompletionDelta { content: Some(text), tool_calls: None };
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Declares a provider registry in one place: a serde-tagged `ProviderConfig`
+/// enum whose variants hold each provider's config fields, plus an `init()`
+/// that builds the matching `Arc<dyn Provider>`. Adding a new provider means
+/// adding one arm here instead of touching every call site that dispatches
+/// on a provider-type string.
+macro_rules! register_provider {
+    ($($variant:ident $name:literal => |$($field:ident : $field_ty:ty),* $(,)?| $ctor:expr),+ $(,)?) => {
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        pub enum ProviderConfig {
+            $(
+                #[serde(rename = $name)]
+                $variant { $($field: $field_ty),* },
+            )+
+        }
+
+        impl ProviderConfig {
+            /// The wire name used to select this variant (its `type` tag value).
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ProviderConfig::$variant { .. } => $name,)+
+                }
+            }
+
+            pub fn init(&self) -> std::sync::Arc<dyn Provider> {
+                match self {
+                    $(ProviderConfig::$variant { $($field),* } => $ctor,)+
+                }
+            }
+        }
+    };
+}
+
+register_provider! {
+    Mock "mock" => || std::sync::Arc::new(MockProvider),
+    LlamaCpp "llama.cpp" => |url: String| std::sync::Arc::new(LlamaCppProvider { url: url.clone() }),
+    OpenAi "openai" => |url: String, api_key: Option<String>, model_id: String| std::sync::Arc::new(OpenAiProvider {
+        url: url.clone(),
+        api_key: api_key.clone(),
+        model_id: model_id.clone(),
+    }),
+    VertexAi "vertex-ai" => |project_id: String, location: String, model: String, credentials_path: Option<String>| std::sync::Arc::new(VertexAiProvider::new(
+        project_id.clone(),
+        location.clone(),
+        model.clone(),
+        credentials_path.clone(),
+    )),
+}
@@ -1,11 +1,13 @@
 use axum::{
-    routing::post,
+    response::{sse::Event, IntoResponse, Response, Sse},
+    routing::{get, post},
     Json, Router, extract::Path, Extension,
 };
 use pecan_core::Agent;
-use pecan_providers::{MockProvider};
+use pecan_providers::{Message, MockProvider, Role};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
 use tokio::sync::Mutex as AsyncMutex;
 use uuid::Uuid;
@@ -32,6 +34,62 @@ struct ChatResponse {
 
 struct AppState {
     sessions: AsyncMutex<HashMap<Uuid, Arc<Agent>>>,
+    /// Backing agent for the OpenAI-compatible endpoints. Callers pass their
+    /// full message history on every request rather than a session id, but
+    /// under the hood this is one shared `Agent` with one history and one
+    /// lifecycle state machine — `chat_completions` holds this mutex for the
+    /// whole request to serialize concurrent calls instead of interleaving
+    /// them, so it behaves correctly but not concurrently.
+    default_agent: AsyncMutex<Option<Arc<Agent>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAiMessage {
+    role: String,
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAiChatRequest {
+    model: Option<String>,
+    messages: Vec<OpenAiMessage>,
+    #[allow(dead_code)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiChoiceMessage {
+    role: String,
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiChoice {
+    index: u32,
+    message: OpenAiChoiceMessage,
+    finish_reason: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiChatResponse {
+    id: String,
+    object: String,
+    model: String,
+    choices: Vec<OpenAiChoice>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiModel {
+    id: String,
+    object: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiModelList {
+    object: String,
+    data: Vec<OpenAiModel>,
 }
 
 #[tokio::main]
@@ -40,11 +98,14 @@ async fn main() {
 
     let state = Arc::new(AppState {
         sessions: AsyncMutex::new(HashMap::new()),
+        default_agent: AsyncMutex::new(None),
     });
 
     let app = Router::new()
         .route("/sessions", post(create_session))
         .route("/sessions/:id/chat", post(chat))
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
         .layer(Extension(state));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -54,12 +115,16 @@ async fn main() {
 
 async fn create_session(
     Extension(state): Extension<Arc<AppState>>,
-    Json(_req): Json<CreateSessionRequest>,
+    Json(req): Json<CreateSessionRequest>,
 ) -> Json<CreateSessionResponse> {
     let session_id = Uuid::new_v4();
     let config = pecan_core::config::Config::load().unwrap();
-    let agent = Arc::new(Agent::new(config, &format!("session_{}", session_id)).await.unwrap());
-    
+    let agent = Arc::new(
+        Agent::new_with_provider(config, &req.provider, &format!("session_{}", session_id))
+            .await
+            .unwrap(),
+    );
+
     let mut sessions = state.sessions.lock().await;
     sessions.insert(session_id, agent);
 
@@ -77,7 +142,7 @@ async fn chat(
         drop(sessions); // Release sessions lock
 
         match agent.chat(req.message).await {
-            Ok(response) => Json(ChatResponse { response }),
+            Ok(status) => Json(ChatResponse { response: status.to_string() }),
             Err(e) => Json(ChatResponse {
                 response: format!("Error: {}", e),
             }),
@@ -88,3 +153,133 @@ async fn chat(
         })
     }
 }
+
+fn openai_role_to_role(role: &str) -> Role {
+    match role {
+        "system" => Role::System,
+        "assistant" => Role::Assistant,
+        "tool" => Role::Tool,
+        _ => Role::User,
+    }
+}
+
+/// `POST /v1/chat/completions` — OpenAI-compatible entry point so any
+/// existing OpenAI SDK, IDE plugin, or CLI can point at Pecan as a drop-in
+/// backend while still getting the agent's tool execution.
+///
+/// Holds `state.default_agent` locked for the entire request (through the
+/// streaming branch too) rather than just the lookup: the one `Agent` behind
+/// it has a single history and lifecycle state machine, so two requests
+/// handled concurrently would clobber each other's history and could trip a
+/// `transition` error. This makes concurrent requests correct by serializing
+/// them, not by giving each one its own isolated agent.
+async fn chat_completions(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(req): Json<OpenAiChatRequest>,
+) -> Response {
+    let mut default_agent = state.default_agent.lock().await;
+    let agent = match default_agent.as_ref() {
+        Some(agent) => agent.clone(),
+        None => {
+            let config = pecan_core::config::Config::load().unwrap();
+            let agent = Arc::new(Agent::new(config, "openai_compat_default").await.unwrap());
+            *default_agent = Some(agent.clone());
+            agent
+        }
+    };
+    let model = req.model.clone().unwrap_or_else(|| "pecan".to_string());
+
+    let messages: Vec<Message> = req
+        .messages
+        .iter()
+        .map(|m| Message {
+            role: openai_role_to_role(&m.role),
+            content: m.content.clone(),
+            tool_calls: None,
+            tool_call_id: None,
+        })
+        .collect();
+
+    if req.stream {
+        return stream_chat_completion(agent, messages, model).await;
+    }
+
+    match agent.chat_with_history(messages).await {
+        Ok(status) => Json(OpenAiChatResponse {
+            id: format!("chatcmpl-{}", Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            model,
+            choices: vec![OpenAiChoice {
+                index: 0,
+                message: OpenAiChoiceMessage {
+                    role: "assistant".to_string(),
+                    content: Some(status.to_string()),
+                },
+                finish_reason: "stop".to_string(),
+            }],
+        })
+        .into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Error: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+/// Runs the agent's tool-calling loop to completion, then replays the
+/// finished answer as a series of `chat.completion.chunk` SSE events. The
+/// tool loop isn't wired for true token-by-token delivery yet, so this
+/// chunks the finished text rather than streaming the model's own tokens.
+async fn stream_chat_completion(agent: Arc<Agent>, messages: Vec<Message>, model: String) -> Response {
+    let id = format!("chatcmpl-{}", Uuid::new_v4());
+
+    let content = match agent.chat_with_history(messages).await {
+        Ok(status) => status.to_string(),
+        Err(e) => format!("Error: {}", e),
+    };
+
+    let mut events: Vec<Result<Event, Infallible>> = content
+        .split_inclusive(' ')
+        .map(|word| {
+            let chunk = serde_json::json!({
+                "id": id,
+                "object": "chat.completion.chunk",
+                "model": model,
+                "choices": [{ "index": 0, "delta": { "content": word }, "finish_reason": serde_json::Value::Null }],
+            });
+            Ok(Event::default().data(chunk.to_string()))
+        })
+        .collect();
+
+    events.push(Ok(Event::default().data(
+        serde_json::json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "model": model,
+            "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }],
+        })
+        .to_string(),
+    )));
+    events.push(Ok(Event::default().data("[DONE]")));
+
+    Sse::new(futures_util::stream::iter(events)).into_response()
+}
+
+/// `GET /v1/models` — lists the configured models in OpenAI's shape.
+async fn list_models() -> Json<OpenAiModelList> {
+    let config = pecan_core::config::Config::load().unwrap_or_default();
+    let data = config
+        .models
+        .keys()
+        .map(|name| OpenAiModel {
+            id: name.clone(),
+            object: "model".to_string(),
+        })
+        .collect();
+
+    Json(OpenAiModelList {
+        object: "list".to_string(),
+        data,
+    })
+}
@@ -0,0 +1,130 @@
+//! Data-driven key bindings. The `keymaps` config section maps action names to
+//! chord strings like `ctrl+shift+tab`; we parse those into crossterm
+//! `KeyCode`/`KeyModifiers` pairs at startup and resolve incoming key events
+//! against them, so the event loop matches on [`Action`]s rather than literal
+//! keys.
+
+use std::collections::HashMap;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A logical input action the TUI loop dispatches on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Complete,
+    Submit,
+    Newline,
+    PauseToggle,
+    NextBuffer,
+    PrevBuffer,
+    ScrollUp,
+    ScrollDown,
+    ThemePicker,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Option<Action> {
+        Some(match name {
+            "quit" => Action::Quit,
+            "complete" => Action::Complete,
+            "submit" => Action::Submit,
+            "newline" => Action::Newline,
+            "pause_toggle" => Action::PauseToggle,
+            "next_buffer" => Action::NextBuffer,
+            "prev_buffer" => Action::PrevBuffer,
+            "scroll_up" => Action::ScrollUp,
+            "scroll_down" => Action::ScrollDown,
+            "theme_picker" => Action::ThemePicker,
+            _ => return None,
+        })
+    }
+}
+
+/// Resolved bindings, searched linearly (the table is tiny).
+pub struct Keybindings {
+    bindings: Vec<((KeyCode, KeyModifiers), Action)>,
+}
+
+impl Keybindings {
+    /// Builds the binding table, returning any human-readable warnings about
+    /// unknown action names or unparsable chords for the caller to surface.
+    pub fn from_config(keymaps: &HashMap<String, String>) -> (Self, Vec<String>) {
+        let mut bindings = Vec::new();
+        let mut warnings = Vec::new();
+        for (name, chord) in keymaps {
+            let Some(action) = Action::from_name(name) else {
+                warnings.push(format!("Unknown keymap action '{}'", name));
+                continue;
+            };
+            match parse_chord(chord) {
+                Some(key) => bindings.push((key, action)),
+                None => warnings.push(format!("Could not parse key chord '{}' for '{}'", chord, name)),
+            }
+        }
+        (Self { bindings }, warnings)
+    }
+
+    /// Resolves a key event to an action, if one is bound.
+    pub fn resolve(&self, key: &KeyEvent) -> Option<Action> {
+        let norm = normalize(key.code, key.modifiers);
+        self.bindings
+            .iter()
+            .find(|(binding, _)| *binding == norm)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Canonicalises a key so equivalent encodings compare equal: `Shift+Tab`
+/// arrives as `BackTab`, and letter chars are lowercased with their shift state
+/// folded into the modifier set.
+fn normalize(code: KeyCode, mods: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::BackTab => (KeyCode::Tab, mods | KeyModifiers::SHIFT),
+        KeyCode::Char(c) if c.is_ascii_uppercase() => {
+            (KeyCode::Char(c.to_ascii_lowercase()), mods | KeyModifiers::SHIFT)
+        }
+        other => (other, mods),
+    }
+}
+
+/// Parses `ctrl+shift+tab` style chords. The final token is the key; any
+/// preceding tokens are modifiers.
+fn parse_chord(chord: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut mods = KeyModifiers::empty();
+    let mut code = None;
+    let tokens: Vec<&str> = chord.split('+').map(str::trim).filter(|t| !t.is_empty()).collect();
+    let last = tokens.len().saturating_sub(1);
+    for (i, token) in tokens.iter().enumerate() {
+        let lower = token.to_lowercase();
+        if i < last {
+            match lower.as_str() {
+                "ctrl" | "control" => mods |= KeyModifiers::CONTROL,
+                "shift" => mods |= KeyModifiers::SHIFT,
+                "alt" | "option" => mods |= KeyModifiers::ALT,
+                _ => return None,
+            }
+        } else {
+            code = Some(match lower.as_str() {
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                s if s.chars().count() == 1 => KeyCode::Char(s.chars().next().unwrap()),
+                _ => return None,
+            });
+        }
+    }
+    Some(normalize(code?, mods))
+}
@@ -1,24 +1,108 @@
 mod theme;
+mod scripting;
+mod tokens;
+mod notify;
+mod keys;
+
+use scripting::{ScriptAction, ScriptEngine};
+use tokens::{format_tokens, TokenMeter};
+use notify::{emit as notify_emit, NotifyEvent};
+use keys::{Action, Keybindings};
 
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Terminal,
 };
 use ratatui::crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use std::collections::HashMap;
 use std::io;
 use std::sync::Arc;
-use pecan_core::Agent;
-use theme::{DRACULA, NORD, DEFAULT};
+use pecan_core::{Agent, AgentEvent, AgentStatus};
+use theme::ThemeRegistry;
 use ratatui_textarea::TextArea;
 
+/// The fixed set of conversation buffers the workspace cycles between. Each
+/// owns an independent transcript, draft, and model selection so a scratch
+/// chat stays separate from a task-driven one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BufferName {
+    Main,
+    Scratch,
+    Task,
+}
+
+impl BufferName {
+    /// Cycle order, also used to render the tab strip.
+    fn all() -> [BufferName; 3] {
+        [BufferName::Main, BufferName::Scratch, BufferName::Task]
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            BufferName::Main => "main",
+            BufferName::Scratch => "scratch",
+            BufferName::Task => "task",
+        }
+    }
+
+    fn parse(s: &str) -> Option<BufferName> {
+        match s.trim().to_lowercase().as_str() {
+            "main" => Some(BufferName::Main),
+            "scratch" => Some(BufferName::Scratch),
+            "task" => Some(BufferName::Task),
+            _ => None,
+        }
+    }
+
+    fn step(&self, forward: bool) -> BufferName {
+        let all = BufferName::all();
+        let i = all.iter().position(|b| b == self).unwrap_or(0);
+        let n = all.len();
+        let j = if forward { (i + 1) % n } else { (i + n - 1) % n };
+        all[j]
+    }
+}
+
+/// One conversation: its transcript, input draft, model, and token tally.
+struct Buffer {
+    messages: Vec<(String, String)>,
+    textarea: TextArea<'static>,
+    current_model: String,
+    token_meter: TokenMeter,
+    thinking: bool,
+    awaiting_approval: Option<String>,
+    /// Count of background events that arrived while this buffer was unfocused;
+    /// cleared when it becomes active.
+    unread: usize,
+    /// Number of transcript items scrolled past from the top (0 shows the start).
+    scroll: usize,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        let mut textarea = TextArea::default();
+        textarea.set_cursor_line_style(Style::default());
+        Self {
+            messages: Vec::new(),
+            textarea,
+            current_model: "default".to_string(),
+            token_meter: TokenMeter::new(),
+            thinking: false,
+            awaiting_approval: None,
+            unread: 0,
+            scroll: 0,
+        }
+    }
+}
+
 pub async fn run_tui(agent: Agent) -> anyhow::Result<()> {
     let is_iterm = std::env::var("TERM_PROGRAM").map(|v| v == "iTerm.app").unwrap_or(false);
     
@@ -59,18 +143,70 @@ async fn run_loop<B: ratatui::prelude::Backend>(
 where
     <B as ratatui::prelude::Backend>::Error: std::error::Error + Send + Sync + 'static,
 {
-    let mut textarea = TextArea::default();
-    textarea.set_cursor_line_style(Style::default());
-    
-    let mut messages: Vec<(String, String)> = Vec::new(); 
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(10);
-    let mut is_thinking = false;
-    let mut current_model = "default".to_string(); 
-    let mut theme = DRACULA;
+    let mut buffers: HashMap<BufferName, Buffer> = HashMap::new();
+    for name in BufferName::all() {
+        buffers.insert(name, Buffer::new());
+    }
+    let mut active = BufferName::Main;
+
+    // Responses are tagged with their originating buffer so a reply lands in
+    // the right transcript even if the user switched away while thinking.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<(BufferName, String)>(10);
+    let theme_registry = ThemeRegistry::load();
+    let mut current_theme_name = { agent.config.lock().await.theme.clone() };
+    let mut theme = theme_registry
+        .resolve(&current_theme_name)
+        .unwrap_or_else(|| theme_registry.resolve("dracula").unwrap());
+
+    // State for the live theme picker overlay opened by the `theme_picker`
+    // keybinding; `None` when the overlay is closed.
+    let mut theme_picker: Option<usize> = None;
 
     let agent = Arc::new(agent);
-    let commands = vec!["/model ", "/theme ", "/quit", "/help", "/clear", "/task ", "/pause", "/resume"];
-    let themes = vec!["dracula", "nord", "default"];
+
+    // The single shared agent is driven by one buffer at a time; streamed
+    // events and approval prompts are routed back to whichever that is.
+    let mut agent_buffer = BufferName::Main;
+
+    // Stream per-step tool events so the user can watch the chain unfold.
+    let (ev_tx, mut ev_rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+    agent.set_event_sender(Some(ev_tx)).await;
+
+    let mut commands: Vec<String> = vec![
+        "/model ", "/theme ", "/buffer ", "/quit", "/help", "/clear", "/task ", "/pause", "/resume",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let themes = theme_registry.names();
+
+    // Load the optional Lua scripting layer and fold its command names into
+    // the completion list the Tab handler consults.
+    let script_engine = match ScriptEngine::load() {
+        Ok(engine) => engine,
+        Err(e) => {
+            buffers.get_mut(&BufferName::Main).unwrap().messages.push((
+                "System".to_string(),
+                format!("Failed to load init.lua: {}", e),
+            ));
+            None
+        }
+    };
+    if let Some(engine) = &script_engine {
+        for name in engine.command_names() {
+            commands.push(format!("/{} ", name));
+        }
+    }
+
+    // Resolve key bindings from config, reporting any bad entries.
+    let keybindings = {
+        let config = agent.config.lock().await;
+        let (bindings, warnings) = Keybindings::from_config(&config.keymaps);
+        for w in warnings {
+            buffers.get_mut(&BufferName::Main).unwrap().messages.push(("System".to_string(), w));
+        }
+        bindings
+    };
 
     let (sep_left, sep_right) = if is_iterm {
         ("\u{e0b0}", "\u{e0b2}") 
@@ -81,15 +217,64 @@ where
     loop {
         let (task_list, is_paused) = {
             let stack = agent.task_stack.lock().await;
-            let paused = agent.paused.lock().await;
-            (stack.tasks.clone(), *paused)
+            (stack.tasks.clone(), agent.is_paused().await)
+        };
+
+        // Focusing a buffer clears its unread marker.
+        buffers.get_mut(&active).unwrap().unread = 0;
+
+        let current_model = buffers[&active].current_model.clone();
+        let total_unread: usize = buffers.values().map(|b| b.unread).sum();
+
+        // The current model's advertised window, defaulting when unset.
+        let context_window = {
+            let config = agent.config.lock().await;
+            config
+                .models
+                .get(&current_model)
+                .and_then(|m| m.context_window)
+                .unwrap_or(8192)
         };
 
+        // Refresh the active buffer's token tally incrementally: only new
+        // transcript entries and a changed draft are re-tokenized here.
+        {
+            let b = buffers.get_mut(&active).unwrap();
+            let draft = b.textarea.lines().join("\n");
+            b.token_meter.sync_history(&b.messages);
+            b.token_meter.sync_draft(&draft);
+        }
+        {
+            let b = buffers.get_mut(&active).unwrap();
+            b.textarea.set_style(Style::default().fg(theme.input_text));
+            b.textarea.set_block(Block::default());
+        }
+        let used_tokens = buffers[&active].token_meter.total();
+        let token_pct = used_tokens * 100 / context_window.max(1);
+        let token_color = if token_pct >= 90 {
+            Color::Red
+        } else if token_pct >= 75 {
+            Color::Yellow
+        } else {
+            theme.status_fg
+        };
+        let token_label = format!(
+            " {} / {} ({}%) ",
+            format_tokens(used_tokens),
+            format_tokens(context_window),
+            token_pct
+        );
+
         terminal.draw(|f| {
+            let buf = &buffers[&active];
+            let textarea = &buf.textarea;
+            let messages = &buf.messages;
+            let is_thinking = buf.thinking;
+
             let input_lines = textarea.lines().len() as u16;
-            let input_height = input_lines.min(10); 
+            let input_height = input_lines.min(10);
 
-            // LAYOUT: 
+            // LAYOUT:
             // Main horizontal split for Sidebar
             let main_chunks = Layout::default()
                 .direction(Direction::Horizontal)
@@ -99,10 +284,11 @@ where
                 ].as_ref())
                 .split(f.area());
 
-            // Left side: Vertical layout for Chat, Dividers, Input, Status
+            // Left side: Vertical layout for Tabs, Chat, Dividers, Input, Status
             let left_chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
+                    Constraint::Length(1),            // Buffer tab strip
                     Constraint::Min(1),               // Chat
                     Constraint::Length(1),            // Divider
                     Constraint::Length(input_height), // Input
@@ -111,6 +297,27 @@ where
                 ].as_ref())
                 .split(main_chunks[0]);
 
+            // 0. Buffer tab strip
+            let mut tab_spans: Vec<Span> = Vec::new();
+            for name in BufferName::all() {
+                let unread = buffers
+                    .get(&name)
+                    .map(|b| b.unread > 0 || b.awaiting_approval.is_some())
+                    .unwrap_or(false);
+                let label = if unread {
+                    format!(" {}* ", name.as_str())
+                } else {
+                    format!(" {} ", name.as_str())
+                };
+                let style = if name == active {
+                    Style::default().bg(theme.status_bg).fg(theme.status_fg).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.border)
+                };
+                tab_spans.push(Span::styled(label, style));
+            }
+            f.render_widget(Paragraph::new(Line::from(tab_spans)), left_chunks[0]);
+
             // 1. Chat Area
             let mut history_items: Vec<ListItem> = messages
                 .iter()
@@ -145,9 +352,13 @@ where
             if is_thinking {
                 history_items.push(ListItem::new(Line::from(" Agent is thinking...").style(Style::default().fg(Color::DarkGray))));
             }
-            
+
+            // Apply the buffer's scroll offset by dropping leading items.
+            let start = buf.scroll.min(history_items.len());
+            let history_items: Vec<ListItem> = history_items.into_iter().skip(start).collect();
+
             let history_list = List::new(history_items);
-            f.render_widget(history_list, left_chunks[0]);
+            f.render_widget(history_list, left_chunks[1]);
 
             // Sidebar: Task Stack
             if !task_list.is_empty() {
@@ -178,15 +389,13 @@ where
             }
 
             // 2. Divider Above Input
-            f.render_widget(Block::default().borders(Borders::BOTTOM).border_style(Style::default().fg(theme.border)), left_chunks[1]);
+            f.render_widget(Block::default().borders(Borders::BOTTOM).border_style(Style::default().fg(theme.border)), left_chunks[2]);
 
             // 3. Input Area
-            textarea.set_style(Style::default().fg(theme.input_text));
-            textarea.set_block(Block::default()); 
-            f.render_widget(&textarea, left_chunks[2]);
+            f.render_widget(textarea, left_chunks[3]);
 
             // 4. Divider Below Input
-            f.render_widget(Block::default().borders(Borders::TOP).border_style(Style::default().fg(theme.border)), left_chunks[3]);
+            f.render_widget(Block::default().borders(Borders::TOP).border_style(Style::default().fg(theme.border)), left_chunks[4]);
 
             // 5. Status Bar
             let status_style = Style::default().bg(theme.status_bg).fg(theme.status_fg);
@@ -196,15 +405,82 @@ where
                 Span::raw(format!(" Model: {} ", current_model)),
                 Span::raw(" | "),
                 Span::raw(if is_paused { "Paused" } else if is_thinking { "Thinking..." } else { "Ready" }),
+                Span::raw(" | "),
+                Span::styled(token_label.clone(), status_style.fg(token_color).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    if total_unread > 0 { format!(" ● {} unread ", total_unread) } else { String::new() },
+                    status_style.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
                 Span::styled(sep_right, Style::default().fg(theme.status_bg).bg(Color::Black)),
             ];
             let status_bar = Paragraph::new(Line::from(status_text));
-            f.render_widget(status_bar, left_chunks[4]);
+            f.render_widget(status_bar, left_chunks[5]);
+
+            // Theme picker overlay: a small centered popup listing every
+            // available theme, the selection highlighted and previewed live.
+            if let Some(idx) = theme_picker {
+                let area = centered_rect(40, 50, f.area());
+                let items: Vec<ListItem> = themes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        let style = if i == idx {
+                            Style::default().bg(theme.highlight).fg(theme.text).add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(theme.text)
+                        };
+                        ListItem::new(Line::from(format!(" {} ", name)).style(style))
+                    })
+                    .collect();
+                let popup = List::new(items).block(
+                    Block::default()
+                        .title(" Theme (↑/↓ preview, Enter select, Esc cancel) ")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.border)),
+                );
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
         })?;
 
-        if let Ok(response) = rx.try_recv() {
-            messages.push(("Agent".to_string(), response));
-            is_thinking = false;
+        // Drain streamed tool-step events into the owning buffer's transcript.
+        while let Ok(event) = ev_rx.try_recv() {
+            let b = buffers.get_mut(&agent_buffer).unwrap();
+            match event {
+                AgentEvent::ToolStarted { name, arguments } => {
+                    b.messages.push(("System".to_string(), format!("→ {} {}", name, arguments)));
+                }
+                AgentEvent::ToolFinished { name, output } => {
+                    b.messages.push(("System".to_string(), format!("← {}: {}", name, output)));
+                }
+                AgentEvent::AwaitingApproval { tool_name, arguments } => {
+                    b.thinking = false;
+                    b.awaiting_approval = Some(tool_name.clone());
+                    b.messages.push((
+                        "System".to_string(),
+                        format!("Tool '{}' requires approval: {}\nApprove? (y/n)", tool_name, arguments),
+                    ));
+                    if agent_buffer != active {
+                        b.unread += 1;
+                    }
+                    let settings = agent.config.lock().await.notifications.clone();
+                    notify_emit(&settings, NotifyEvent::Approval, &format!("Tool '{}' needs approval", tool_name));
+                }
+            }
+        }
+
+        if let Ok((origin, response)) = rx.try_recv() {
+            if let Some(b) = buffers.get_mut(&origin) {
+                b.messages.push(("Agent".to_string(), response.clone()));
+                b.thinking = false;
+                if origin != active {
+                    b.unread += 1;
+                }
+            }
+            if let Some(engine) = &script_engine {
+                let actions = engine.on_agent_response(&response);
+                apply_script_actions(actions, &agent, &mut buffers, origin, &tx).await;
+            }
         }
 
         if event::poll(std::time::Duration::from_millis(50))? {
@@ -214,17 +490,75 @@ where
                     continue;
                 }
 
-                if key.modifiers.contains(event::KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-                    return Ok(());
-                }
-
-                if is_thinking {
-                    // Even if thinking, allow pause/resume?
+                // The theme picker overlay owns the keyboard while open: arrows
+                // preview a theme live, Enter confirms and persists it, Esc backs
+                // out to whatever was active before the overlay was opened.
+                if let Some(idx) = theme_picker {
+                    match key.code {
+                        event::KeyCode::Down => {
+                            let next = (idx + 1) % themes.len();
+                            theme = theme_registry.resolve(&themes[next]).unwrap();
+                            theme_picker = Some(next);
+                        }
+                        event::KeyCode::Up => {
+                            let next = (idx + themes.len() - 1) % themes.len();
+                            theme = theme_registry.resolve(&themes[next]).unwrap();
+                            theme_picker = Some(next);
+                        }
+                        event::KeyCode::Enter => {
+                            current_theme_name = themes[idx].clone();
+                            persist_theme(&agent, &current_theme_name).await;
+                            theme_picker = None;
+                        }
+                        event::KeyCode::Esc => {
+                            theme = theme_registry.resolve(&current_theme_name).unwrap();
+                            theme_picker = None;
+                        }
+                        _ => {}
+                    }
+                    continue;
                 }
 
-                match key.code {
-                    KeyCode::Tab => {
-                        let line = &textarea.lines()[0]; 
+                // Resolve the key through the configured bindings; unbound keys
+                // fall through to the text editor.
+                match keybindings.resolve(&key) {
+                    Some(Action::Quit) => return Ok(()),
+                    Some(Action::ThemePicker) => {
+                        let idx = themes.iter().position(|t| *t == current_theme_name).unwrap_or(0);
+                        theme_picker = Some(idx);
+                    }
+                    Some(Action::NextBuffer) => {
+                        active = active.step(true);
+                    }
+                    Some(Action::PrevBuffer) => {
+                        active = active.step(false);
+                    }
+                    Some(Action::ScrollUp) => {
+                        let b = buffers.get_mut(&active).unwrap();
+                        b.scroll = b.scroll.saturating_sub(1);
+                    }
+                    Some(Action::ScrollDown) => {
+                        let b = buffers.get_mut(&active).unwrap();
+                        b.scroll += 1;
+                    }
+                    Some(Action::Newline) => {
+                        buffers.get_mut(&active).unwrap().textarea.insert_newline();
+                    }
+                    Some(Action::PauseToggle) => {
+                        let result = if agent.is_paused().await {
+                            agent.resume().await.map(|_| "Autonomous loop resumed.".to_string())
+                        } else {
+                            agent.pause().await.map(|_| "Autonomous loop paused.".to_string())
+                        };
+                        let msg = match result {
+                            Ok(m) => m,
+                            Err(e) => format!("Could not toggle pause: {}", e),
+                        };
+                        buffers.get_mut(&active).unwrap().messages.push(("System".to_string(), msg));
+                    }
+                    Some(Action::Complete) => {
+                        let buf = buffers.get_mut(&active).unwrap();
+                        let line = buf.textarea.lines()[0].clone();
                         if line.starts_with("/model ") {
                             let partial = &line["/model ".len()..];
                             let config = agent.config.lock().await;
@@ -232,8 +566,8 @@ where
                                 .filter(|m| m.starts_with(partial))
                                 .collect();
                             if matches.len() == 1 {
-                                textarea.delete_line_by_head();
-                                textarea.insert_str(format!("/model {}", matches[0]));
+                                buf.textarea.delete_line_by_head();
+                                buf.textarea.insert_str(format!("/model {}", matches[0]));
                             }
                         } else if line.starts_with("/theme ") {
                             let partial = &line["/theme ".len()..];
@@ -241,80 +575,140 @@ where
                                 .filter(|t| t.starts_with(partial))
                                 .collect();
                             if matches.len() == 1 {
-                                textarea.delete_line_by_head();
-                                textarea.insert_str(format!("/theme {}", matches[0]));
+                                buf.textarea.delete_line_by_head();
+                                buf.textarea.insert_str(format!("/theme {}", matches[0]));
                             }
-                        } else if line.starts_with("/") {
+                        } else if line.starts_with('/') {
                             let matches: Vec<_> = commands.iter()
-                                .filter(|c| c.starts_with(line))
+                                .filter(|c| c.starts_with(&line))
                                 .collect();
                             if matches.len() == 1 {
-                                textarea.delete_line_by_head();
-                                textarea.insert_str(matches[0]);
+                                buf.textarea.delete_line_by_head();
+                                buf.textarea.insert_str(matches[0].clone());
                             }
                         }
                     }
-                    KeyCode::Enter if !key.modifiers.contains(event::KeyModifiers::SHIFT) => {
-                        let user_input = textarea.lines().join("\n");
-                        textarea.move_cursor(ratatui_textarea::CursorMove::End);
-                        while !textarea.is_empty() {
-                            textarea.delete_line_by_head();
-                        }
-                        
+                    Some(Action::Submit) => {
+                        let user_input = {
+                            let buf = buffers.get_mut(&active).unwrap();
+                            let input = buf.textarea.lines().join("\n");
+                            buf.textarea.move_cursor(ratatui_textarea::CursorMove::End);
+                            while !buf.textarea.is_empty() {
+                                buf.textarea.delete_line_by_head();
+                            }
+                            input
+                        };
+
                         if user_input.trim().is_empty() {
                             continue;
                         }
-                        
+
+                        // A buffer awaiting approval treats the next line as its y/n decision.
+                        let pending_approval = buffers.get_mut(&active).unwrap().awaiting_approval.take();
+                        if pending_approval.is_some() {
+                            let approve = matches!(user_input.trim().to_lowercase().as_str(), "y" | "yes");
+                            let buf = buffers.get_mut(&active).unwrap();
+                            buf.messages.push(("You".to_string(), user_input.clone()));
+                            buf.thinking = true;
+                            agent_buffer = active;
+                            let target = active;
+                            let agent_clone = agent.clone();
+                            let tx_clone = tx.clone();
+                            tokio::spawn(async move {
+                                let result = if approve {
+                                    agent_clone.approve_tool_call().await
+                                } else {
+                                    agent_clone.reject_tool_call("Rejected by user").await
+                                };
+                                match result {
+                                    Ok(AgentStatus::Response(text)) => {
+                                        let _ = tx_clone.send((target, text)).await;
+                                    }
+                                    // Further approvals surface through the event stream.
+                                    Ok(AgentStatus::AwaitingApproval { .. }) => {}
+                                    Err(e) => {
+                                        let _ = tx_clone.send((target, format!("Error: {}", e))).await;
+                                    }
+                                }
+                            });
+                            continue;
+                        }
+
+                        if user_input.starts_with("/buffer ") {
+                            let name = &user_input["/buffer ".len()..];
+                            match BufferName::parse(name) {
+                                Some(n) => active = n,
+                                None => buffers.get_mut(&active).unwrap().messages.push((
+                                    "System".to_string(),
+                                    format!("Unknown buffer: {}. Available: main, scratch, task", name.trim()),
+                                )),
+                            }
+                            continue;
+                        }
+
                         if user_input.starts_with("/pause") {
-                            let mut paused = agent.paused.lock().await;
-                            *paused = true;
-                            messages.push(("System".to_string(), "Autonomous loop paused.".to_string()));
+                            let msg = match agent.pause().await {
+                                Ok(_) => "Autonomous loop paused.".to_string(),
+                                Err(e) => format!("Could not pause: {}", e),
+                            };
+                            buffers.get_mut(&active).unwrap().messages.push(("System".to_string(), msg));
                             continue;
                         }
 
                         if user_input.starts_with("/resume") {
-                            let mut paused = agent.paused.lock().await;
-                            *paused = false;
-                            messages.push(("System".to_string(), "Autonomous loop resumed.".to_string()));
+                            let msg = match agent.resume().await {
+                                Ok(_) => "Autonomous loop resumed.".to_string(),
+                                Err(e) => format!("Could not resume: {}", e),
+                            };
+                            buffers.get_mut(&active).unwrap().messages.push(("System".to_string(), msg));
                             continue;
                         }
 
                         if user_input.starts_with("/model ") {
                             let model_name = user_input["/model ".len()..].trim().to_string();
-                            current_model = model_name.clone();
+                            buffers.get_mut(&active).unwrap().current_model = model_name.clone();
+                            let target = active;
                             let agent_clone = agent.clone();
                             let tx_clone = tx.clone();
                             tokio::spawn(async move {
-                                match agent_clone.switch_model(&model_name).await {
-                                    Ok(_) => {
-                                        let _ = tx_clone.send(format!("Switched to model: {}", model_name)).await;
-                                    }
-                                    Err(e) => {
-                                        let _ = tx_clone.send(format!("Failed to switch model: {}", e)).await;
-                                    }
-                                }
+                                let msg = match agent_clone.switch_model(&model_name).await {
+                                    Ok(_) => format!("Switched to model: {}", model_name),
+                                    Err(e) => format!("Failed to switch model: {}", e),
+                                };
+                                let _ = tx_clone.send((target, msg)).await;
                             });
                             continue;
                         }
 
                         if user_input.starts_with("/theme ") {
                             let theme_name = user_input["/theme ".len()..].trim().to_lowercase();
-                            match theme_name.as_str() {
-                                "dracula" => theme = DRACULA,
-                                "nord" => theme = NORD,
-                                "default" => theme = DEFAULT,
-                                _ => messages.push(("System".to_string(), format!("Unknown theme: {}. Available: dracula, nord, default", theme_name))),
+                            match theme_registry.resolve(&theme_name) {
+                                Some(resolved) => {
+                                    theme = resolved;
+                                    current_theme_name = theme_name.clone();
+                                    persist_theme(&agent, &theme_name).await;
+                                }
+                                None => buffers.get_mut(&active).unwrap().messages.push((
+                                    "System".to_string(),
+                                    format!(
+                                        "Unknown theme: {}. Available: {}",
+                                        theme_name,
+                                        themes.join(", ")
+                                    ),
+                                )),
                             }
                             continue;
                         }
 
                         if user_input.trim() == "/clear" {
-                            messages.clear();
+                            buffers.get_mut(&active).unwrap().messages.clear();
                             continue;
                         }
 
                         if user_input.starts_with("/task ") {
                             let task_desc = user_input["/task ".len()..].trim().to_string();
+                            agent_buffer = active;
+                            let target = active;
                             let agent_clone = agent.clone();
                             let tx_clone = tx.clone();
                             tokio::spawn(async move {
@@ -322,15 +716,14 @@ where
                                     let mut stack = agent_clone.task_stack.lock().await;
                                     stack.push(task_desc.clone());
                                 }
-                                let _ = tx_clone.send(format!("Started autonomous task: {}", task_desc)).await;
-                                match agent_clone.run_autonomous_loop().await {
-                                    Ok(_) => {
-                                        let _ = tx_clone.send("Autonomous loop finished.".to_string()).await;
-                                    }
-                                    Err(e) => {
-                                        let _ = tx_clone.send(format!("Autonomous loop failed: {}", e)).await;
-                                    }
-                                }
+                                let _ = tx_clone.send((target, format!("Started autonomous task: {}", task_desc))).await;
+                                let msg = match agent_clone.run_autonomous_loop().await {
+                                    Ok(_) => "Autonomous loop finished.".to_string(),
+                                    Err(e) => format!("Autonomous loop failed: {}", e),
+                                };
+                                let settings = agent_clone.config.lock().await.notifications.clone();
+                                notify_emit(&settings, NotifyEvent::TaskComplete, &msg);
+                                let _ = tx_clone.send((target, msg)).await;
                             });
                             continue;
                         }
@@ -340,31 +733,126 @@ where
                         }
 
                         if user_input.trim() == "/help" {
-                            messages.push(("System".to_string(), "Commands: /model <name>, /theme <name>, /task <desc>, /pause, /resume, /clear, /quit, /help".to_string()));
+                            buffers.get_mut(&active).unwrap().messages.push((
+                                "System".to_string(),
+                                "Commands: /model <name>, /theme <name>, /buffer <name>, /task <desc>, /pause, /resume, /clear, /quit, /help".to_string(),
+                            ));
                             continue;
                         }
 
-                        messages.push(("You".to_string(), user_input.clone()));
-                        is_thinking = true;
-                        
+                        // User-defined Lua slash-commands take precedence over a chat send.
+                        if let Some(engine) = &script_engine {
+                            if let Some(rest) = user_input.strip_prefix('/') {
+                                let (name, arg) = match rest.split_once(char::is_whitespace) {
+                                    Some((n, a)) => (n.to_string(), a.trim().to_string()),
+                                    None => (rest.to_string(), String::new()),
+                                };
+                                if engine.has_command(&name) {
+                                    let actions = engine.run_command(&name, &arg);
+                                    apply_script_actions(actions, &agent, &mut buffers, active, &tx).await;
+                                    continue;
+                                }
+                            }
+                        }
+
+                        {
+                            let buf = buffers.get_mut(&active).unwrap();
+                            buf.messages.push(("You".to_string(), user_input.clone()));
+                            buf.thinking = true;
+                        }
+                        agent_buffer = active;
+                        let target = active;
                         let agent_clone = agent.clone();
                         let tx_clone = tx.clone();
                         tokio::spawn(async move {
                             match agent_clone.chat(user_input).await {
-                                Ok(response) => {
-                                    let _ = tx_clone.send(response).await;
+                                Ok(AgentStatus::Response(text)) => {
+                                    let _ = tx_clone.send((target, text)).await;
                                 }
+                                // Approval prompts are surfaced via the event stream.
+                                Ok(AgentStatus::AwaitingApproval { .. }) => {}
                                 Err(e) => {
-                                    let _ = tx_clone.send(format!("Error: {}", e)).await;
+                                    let _ = tx_clone.send((target, format!("Error: {}", e))).await;
                                 }
                             }
                         });
                     }
-                    _ => {
-                        textarea.input(key);
+                    None => {
+                        buffers.get_mut(&active).unwrap().textarea.input(key);
                     }
                 }
             }
         }
     }
 }
+
+/// Carves a `percent_x` × `percent_y` rectangle out of the centre of `r`, for
+/// floating overlays like the theme picker.
+fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Writes `name` into the shared config's `theme` field and saves it to disk,
+/// so the picker's selection survives a restart. Failures are non-fatal; the
+/// session keeps running with the new theme even if the write failed.
+async fn persist_theme(agent: &Arc<Agent>, name: &str) {
+    let mut config = agent.config.lock().await;
+    config.theme = name.to_string();
+    if let Err(e) = config.save() {
+        tracing::warn!("Failed to persist theme '{}': {}", name, e);
+    }
+}
+
+/// Applies the side effects a Lua callback requested back on the async UI loop,
+/// targeting the buffer the callback was triggered from.
+async fn apply_script_actions(
+    actions: Vec<ScriptAction>,
+    agent: &Arc<Agent>,
+    buffers: &mut HashMap<BufferName, Buffer>,
+    target: BufferName,
+    tx: &tokio::sync::mpsc::Sender<(BufferName, String)>,
+) {
+    for action in actions {
+        match action {
+            ScriptAction::SendMessage { role, text } => {
+                if let Some(b) = buffers.get_mut(&target) {
+                    b.messages.push((role, text));
+                }
+            }
+            ScriptAction::PushTask { description } => {
+                let mut stack = agent.task_stack.lock().await;
+                stack.push(description);
+            }
+            ScriptAction::SwitchModel { name } => {
+                if let Some(b) = buffers.get_mut(&target) {
+                    b.current_model = name.clone();
+                }
+                let agent_clone = agent.clone();
+                let tx_clone = tx.clone();
+                tokio::spawn(async move {
+                    let msg = match agent_clone.switch_model(&name).await {
+                        Ok(_) => format!("Switched to model: {}", name),
+                        Err(e) => format!("Failed to switch model: {}", e),
+                    };
+                    let _ = tx_clone.send((target, msg)).await;
+                });
+            }
+        }
+    }
+}
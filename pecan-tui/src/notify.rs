@@ -0,0 +1,46 @@
+//! "Something needs you" signalling for backgrounded work. When an autonomous
+//! loop finishes or a tool awaits approval we optionally ring the terminal bell
+//! and raise an OS desktop notification, gated by the `notifications` config
+//! block. The status bar's unread counter is maintained separately by the loop.
+
+use std::io::Write;
+
+use pecan_core::config::NotificationConfig;
+
+/// The kinds of background events worth interrupting the user for.
+#[derive(Debug, Clone, Copy)]
+pub enum NotifyEvent {
+    TaskComplete,
+    Approval,
+}
+
+/// Emits the configured signals for `event`, honouring the per-event flags.
+pub fn emit(settings: &NotificationConfig, event: NotifyEvent, body: &str) {
+    if !settings.enabled {
+        return;
+    }
+    let wanted = match event {
+        NotifyEvent::TaskComplete => settings.on_task_complete,
+        NotifyEvent::Approval => settings.on_approval,
+    };
+    if !wanted {
+        return;
+    }
+
+    if settings.bell {
+        // The bell byte reaches the terminal even under the alternate screen.
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(b"\x07");
+        let _ = stdout.flush();
+    }
+
+    if settings.desktop {
+        let summary = match event {
+            NotifyEvent::TaskComplete => "pecan: task finished",
+            NotifyEvent::Approval => "pecan: approval needed",
+        };
+        if let Err(e) = notify_rust::Notification::new().summary(summary).body(body).show() {
+            tracing::warn!("Desktop notification failed: {}", e);
+        }
+    }
+}
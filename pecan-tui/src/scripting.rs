@@ -0,0 +1,219 @@
+//! Embedded Lua scripting layer. On startup we load `~/.pecan/init.lua` (next to
+//! `Config::get_config_path`) and expose a `pecan` table so users can register
+//! custom slash-commands and response hooks without recompiling.
+//!
+//! The Lua state is not `Send`, so it lives on its own OS thread; the UI talks to
+//! it over `std::sync::mpsc` channels and applies the resulting [`ScriptAction`]s
+//! back on the async loop.
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+/// A side effect requested by a Lua callback, applied by the TUI loop.
+#[derive(Debug, Clone)]
+pub enum ScriptAction {
+    SendMessage { role: String, text: String },
+    PushTask { description: String },
+    SwitchModel { name: String },
+}
+
+enum Request {
+    Command {
+        name: String,
+        arg: String,
+        reply: mpsc::Sender<Vec<ScriptAction>>,
+    },
+    AgentResponse {
+        text: String,
+        reply: mpsc::Sender<Vec<ScriptAction>>,
+    },
+}
+
+/// Handle to the Lua interpreter thread.
+pub struct ScriptEngine {
+    tx: mpsc::Sender<Request>,
+    command_names: Vec<String>,
+}
+
+/// Resolves the path to the user's init script, alongside the config file.
+pub fn init_lua_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".pecan").join("init.lua"))
+}
+
+impl ScriptEngine {
+    /// Loads `init.lua` if present, spawning the interpreter thread. Returns
+    /// `Ok(None)` when the user has no script.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let path = match init_lua_path() {
+            Some(p) if p.exists() => p,
+            _ => return Ok(None),
+        };
+
+        let (tx, rx) = mpsc::channel::<Request>();
+        let (names_tx, names_rx) = mpsc::channel::<Vec<String>>();
+
+        std::thread::spawn(move || {
+            if let Err(e) = engine_thread(&path, rx, names_tx) {
+                tracing::error!("Lua engine thread exited: {}", e);
+            }
+        });
+
+        // Block briefly for the command list produced while running init.lua.
+        let command_names = names_rx.recv().unwrap_or_default();
+        Ok(Some(Self { tx, command_names }))
+    }
+
+    /// Command names (without the leading slash) registered by the script.
+    pub fn command_names(&self) -> &[String] {
+        &self.command_names
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        self.command_names.iter().any(|c| c == name)
+    }
+
+    /// Invokes a registered command, returning the actions it requested.
+    pub fn run_command(&self, name: &str, arg: &str) -> Vec<ScriptAction> {
+        let (reply, reply_rx) = mpsc::channel();
+        if self
+            .tx
+            .send(Request::Command {
+                name: name.to_string(),
+                arg: arg.to_string(),
+                reply,
+            })
+            .is_err()
+        {
+            return Vec::new();
+        }
+        reply_rx.recv().unwrap_or_default()
+    }
+
+    /// Fires every `on_agent_response` hook with the agent's reply text.
+    pub fn on_agent_response(&self, text: &str) -> Vec<ScriptAction> {
+        let (reply, reply_rx) = mpsc::channel();
+        if self
+            .tx
+            .send(Request::AgentResponse {
+                text: text.to_string(),
+                reply,
+            })
+            .is_err()
+        {
+            return Vec::new();
+        }
+        reply_rx.recv().unwrap_or_default()
+    }
+}
+
+fn engine_thread(
+    path: &std::path::Path,
+    rx: mpsc::Receiver<Request>,
+    names_tx: mpsc::Sender<Vec<String>>,
+) -> anyhow::Result<()> {
+    use mlua::{Lua, RegistryKey};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    let lua = Lua::new();
+
+    // Shared buffer the `pecan` helpers append to while a callback runs.
+    let actions: Rc<RefCell<Vec<ScriptAction>>> = Rc::new(RefCell::new(Vec::new()));
+    let commands: Rc<RefCell<HashMap<String, RegistryKey>>> = Rc::new(RefCell::new(HashMap::new()));
+    let response_hooks: Rc<RefCell<Vec<RegistryKey>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let pecan = lua.create_table()?;
+
+    {
+        let commands = commands.clone();
+        let register = lua.create_function(move |lua, (name, func): (String, mlua::Function)| {
+            let key = lua.create_registry_value(func)?;
+            commands.borrow_mut().insert(name, key);
+            Ok(())
+        })?;
+        pecan.set("register_command", register)?;
+    }
+
+    {
+        let response_hooks = response_hooks.clone();
+        let on_response = lua.create_function(move |lua, func: mlua::Function| {
+            let key = lua.create_registry_value(func)?;
+            response_hooks.borrow_mut().push(key);
+            Ok(())
+        })?;
+        pecan.set("on_agent_response", on_response)?;
+    }
+
+    {
+        let actions = actions.clone();
+        let send_message = lua.create_function(move |_, (role, text): (String, String)| {
+            actions.borrow_mut().push(ScriptAction::SendMessage { role, text });
+            Ok(())
+        })?;
+        pecan.set("send_message", send_message)?;
+    }
+
+    {
+        let actions = actions.clone();
+        let push_task = lua.create_function(move |_, description: String| {
+            actions.borrow_mut().push(ScriptAction::PushTask { description });
+            Ok(())
+        })?;
+        pecan.set("push_task", push_task)?;
+    }
+
+    {
+        let actions = actions.clone();
+        let switch_model = lua.create_function(move |_, name: String| {
+            actions.borrow_mut().push(ScriptAction::SwitchModel { name });
+            Ok(())
+        })?;
+        pecan.set("switch_model", switch_model)?;
+    }
+
+    lua.globals().set("pecan", pecan)?;
+
+    // Run the user's init script; failures are reported but non-fatal.
+    let source = std::fs::read_to_string(path)?;
+    if let Err(e) = lua.load(&source).set_name("init.lua").exec() {
+        tracing::error!("Error running init.lua: {}", e);
+    }
+
+    let names: Vec<String> = commands.borrow().keys().cloned().collect();
+    let _ = names_tx.send(names);
+
+    for request in rx {
+        match request {
+            Request::Command { name, arg, reply } => {
+                actions.borrow_mut().clear();
+                let func: Option<mlua::Function> = commands
+                    .borrow()
+                    .get(&name)
+                    .and_then(|k| lua.registry_value(k).ok());
+                if let Some(func) = func {
+                    if let Err(e) = func.call::<()>(arg) {
+                        tracing::error!("Lua command '{}' failed: {}", name, e);
+                    }
+                }
+                let _ = reply.send(actions.borrow_mut().drain(..).collect());
+            }
+            Request::AgentResponse { text, reply } => {
+                actions.borrow_mut().clear();
+                let keys: Vec<mlua::Function> = response_hooks
+                    .borrow()
+                    .iter()
+                    .filter_map(|k| lua.registry_value(k).ok())
+                    .collect();
+                for func in keys {
+                    if let Err(e) = func.call::<()>(text.clone()) {
+                        tracing::error!("Lua on_agent_response hook failed: {}", e);
+                    }
+                }
+                let _ = reply.send(actions.borrow_mut().drain(..).collect());
+            }
+        }
+    }
+
+    Ok(())
+}
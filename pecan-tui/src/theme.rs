@@ -1,17 +1,32 @@
+use palette::{FromColor, Hue, Oklab, Oklch, Srgb};
 use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize)]
 pub struct Theme {
+    #[serde(deserialize_with = "deserialize_color")]
     pub border: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub text: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub highlight: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub header_bg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub header_fg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub status_bg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub status_fg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub input_text: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub agent_text: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub user_bg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub user_fg: Color,
 }
 
@@ -70,3 +85,330 @@ pub const LIGHT: Theme = Theme {
     user_bg: Color::Rgb(235, 235, 235), // Light gray background for user
     user_fg: Color::Rgb(50, 50, 50), // Dark gray text for user
 };
+
+/// Minimum contrast ratio the WCAG AA standard requires for normal text.
+const MIN_CONTRAST: f64 = 4.5;
+
+impl Theme {
+    /// Builds a full `Theme` from just a background, a text color, and one
+    /// accent, deriving the rest in Oklab so new palettes don't need eleven
+    /// hand-picked values. Subdued backgrounds (`header_bg`, `status_bg`,
+    /// `user_bg`, `border`) are `bg` nudged toward the opposite end of the
+    /// lightness scale by a fixed delta; hue-rotated copies of `accent` give
+    /// `highlight`, `input_text`, and `agent_text` distinct identities instead
+    /// of all collapsing onto one color. Every `*_fg`/`*_bg` pair is then
+    /// nudged in lightness until it clears [`MIN_CONTRAST`].
+    pub fn from_base(bg: Color, fg: Color, accent: Color) -> Theme {
+        let bg_rgb = color_to_rgb8(bg);
+        let bg_lab = to_oklab(bg_rgb);
+        let subdued = |delta: f32| from_oklab(Oklab::new(nudge_lightness(bg_lab.l, delta), bg_lab.a, bg_lab.b));
+
+        let header_bg = subdued(0.06);
+        let status_bg = subdued(0.09);
+        let user_bg = subdued(0.04);
+        let border = subdued(0.12);
+
+        let highlight = rotate_hue(accent, 0.0);
+        let input_text = rotate_hue(accent, 35.0);
+        let agent_text = rotate_hue(accent, -35.0);
+
+        Theme {
+            border: rgb8_to_color(border),
+            text: rgb8_to_color(ensure_contrast(color_to_rgb8(fg), bg_rgb)),
+            highlight: rgb8_to_color(ensure_contrast(highlight, bg_rgb)),
+            header_bg: rgb8_to_color(header_bg),
+            header_fg: rgb8_to_color(ensure_contrast(color_to_rgb8(fg), header_bg)),
+            status_bg: rgb8_to_color(status_bg),
+            status_fg: rgb8_to_color(ensure_contrast(highlight, status_bg)),
+            input_text: rgb8_to_color(ensure_contrast(input_text, bg_rgb)),
+            agent_text: rgb8_to_color(ensure_contrast(agent_text, bg_rgb)),
+            user_bg: rgb8_to_color(user_bg),
+            user_fg: rgb8_to_color(ensure_contrast(color_to_rgb8(fg), user_bg)),
+        }
+    }
+
+    /// Picks `LIGHT` or `DRACULA` by querying the terminal's real background
+    /// color over OSC 11 and computing its perceived luminance, falling back
+    /// to `DEFAULT` when the terminal doesn't answer (or answers with
+    /// something unparsable) within the timeout. Backs the `theme = "auto"`
+    /// config option.
+    pub fn auto() -> Theme {
+        match query_background_color() {
+            Some((r, g, b)) => {
+                let luminance = 0.2126 * r as f64 / 255.0
+                    + 0.7152 * g as f64 / 255.0
+                    + 0.0722 * b as f64 / 255.0;
+                if luminance > 0.5 {
+                    LIGHT
+                } else {
+                    DRACULA
+                }
+            }
+            None => DEFAULT,
+        }
+    }
+}
+
+const OSC11_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Sends `\x1b]11;?\x07` and waits on a background thread for the terminal's
+/// `\x1b]11;rgb:RRRR/GGGG/BBBB` reply, terminated by BEL or ST. The read runs
+/// on its own thread so a terminal that never answers can't hang startup;
+/// `recv_timeout` just gives up and leaves the thread to exit on its own
+/// whenever (if ever) the read unblocks. On a terminal that doesn't support
+/// OSC 11 at all, that abandoned thread can end up stealing bytes the main
+/// event loop was expecting — `ThemeRegistry::resolve` only calls this at
+/// most once, and only for the `"auto"` theme, so that cost is confined to
+/// sessions that actually opt into it (the same trade-off crossterm's own
+/// `cursor::position()` query makes).
+fn query_background_color() -> Option<(u8, u8, u8)> {
+    use std::io::{Read, Write};
+
+    std::io::stdout().write_all(b"\x1b]11;?\x07").ok()?;
+    std::io::stdout().flush().ok()?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut reply = Vec::new();
+        let mut byte = [0u8; 1];
+        while reply.len() < 64 {
+            if stdin.read_exact(&mut byte).is_err() {
+                break;
+            }
+            reply.push(byte[0]);
+            if byte[0] == 0x07 || reply.ends_with(b"\x1b\\") {
+                break;
+            }
+        }
+        let _ = tx.send(reply);
+    });
+
+    let reply = rx.recv_timeout(OSC11_QUERY_TIMEOUT).ok()?;
+    parse_osc11_reply(&reply)
+}
+
+/// Extracts the `RRRR/GGGG/BBBB` triplet from an OSC 11 reply, scaling each
+/// 16-bit channel down to 8 bits.
+fn parse_osc11_reply(reply: &[u8]) -> Option<(u8, u8, u8)> {
+    let text = String::from_utf8_lossy(reply);
+    let rgb = text.split("rgb:").nth(1)?;
+    let mut channels = rgb.splitn(3, '/');
+    let r = parse_channel(channels.next()?)?;
+    let g = parse_channel(channels.next()?)?;
+    let b = parse_channel(channels.next()?)?;
+    Some((r, g, b))
+}
+
+fn parse_channel(s: &str) -> Option<u8> {
+    let hex: String = s.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+    let value = u16::from_str_radix(&hex, 16).ok()?;
+    Some((value >> 8) as u8)
+}
+
+/// Resolves a ratatui `Color` to 8-bit RGB for the `palette` conversions
+/// `Theme::from_base` needs. Named colors use their standard terminal RGB
+/// approximations; `Indexed` falls back to mid-gray since the real palette
+/// entry depends on the terminal's color scheme.
+fn color_to_rgb8(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        _ => (127, 127, 127),
+    }
+}
+
+fn rgb8_to_color((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb(r, g, b)
+}
+
+fn to_oklab((r, g, b): (u8, u8, u8)) -> Oklab {
+    Oklab::from_color(Srgb::new(r, g, b).into_format::<f32>())
+}
+
+fn from_oklab(lab: Oklab) -> (u8, u8, u8) {
+    let srgb: Srgb<u8> = Srgb::from_color(lab).into_format();
+    (srgb.red, srgb.green, srgb.blue)
+}
+
+/// Shifts `color`'s Oklch hue by `degrees`, keeping lightness and chroma, so
+/// derived slots like `input_text`/`agent_text` read as distinct colors
+/// rather than repeats of the same accent.
+fn rotate_hue(color: Color, degrees: f32) -> (u8, u8, u8) {
+    let (r, g, b) = color_to_rgb8(color);
+    let oklch = Oklch::from_color(Srgb::new(r, g, b).into_format::<f32>());
+    let rotated = oklch.shift_hue(degrees);
+    let srgb: Srgb<u8> = Srgb::from_color(rotated).into_format();
+    (srgb.red, srgb.green, srgb.blue)
+}
+
+/// Nudges an Oklab lightness (0.0-1.0) by `delta` toward whichever end of the
+/// scale is farther away, so a dark background gets a lighter subdued variant
+/// and a light background gets a darker one.
+fn nudge_lightness(l: f32, delta: f32) -> f32 {
+    if l < 0.5 {
+        (l + delta).min(1.0)
+    } else {
+        (l - delta).max(0.0)
+    }
+}
+
+/// WCAG 2.x relative luminance from 8-bit sRGB.
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let linearize = |c: u8| {
+        let cs = c as f64 / 255.0;
+        if cs <= 0.03928 {
+            cs / 12.92
+        } else {
+            ((cs + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linearize(r) + 0.7152 * linearize(g) + 0.0722 * linearize(b)
+}
+
+/// WCAG contrast ratio between two colors, always ≥ 1.0.
+fn contrast_ratio(a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (hi, lo) = if la > lb { (la, lb) } else { (lb, la) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+/// Nudges `fg`'s Oklab lightness away from `bg`'s until their contrast ratio
+/// clears [`MIN_CONTRAST`], or 50 steps run out (by which point it's already
+/// at white or black).
+fn ensure_contrast(fg: (u8, u8, u8), bg: (u8, u8, u8)) -> (u8, u8, u8) {
+    if contrast_ratio(fg, bg) >= MIN_CONTRAST {
+        return fg;
+    }
+    let lighten = relative_luminance(bg) < 0.5;
+    let mut lab = to_oklab(fg);
+    for _ in 0..50 {
+        if contrast_ratio(from_oklab(lab), bg) >= MIN_CONTRAST {
+            break;
+        }
+        lab.l = if lighten { (lab.l + 0.02).min(1.0) } else { (lab.l - 0.02).max(0.0) };
+    }
+    from_oklab(lab)
+}
+
+/// Parses a `#rrggbb`, `0xrrggbb`, or a small set of named colors into a
+/// ratatui `Color`. Used as the `deserialize_with` for every `Theme` field
+/// so a user's TOML file can write colors as plain hex strings.
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    parse_color(&raw).map_err(serde::de::Error::custom)
+}
+
+fn parse_color(raw: &str) -> anyhow::Result<Color> {
+    let hex = raw.strip_prefix('#').or_else(|| raw.strip_prefix("0x")).unwrap_or(raw);
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        let r = u8::from_str_radix(&hex[0..2], 16)?;
+        let g = u8::from_str_radix(&hex[2..4], 16)?;
+        let b = u8::from_str_radix(&hex[4..6], 16)?;
+        return Ok(Color::Rgb(r, g, b));
+    }
+
+    match raw.to_lowercase().as_str() {
+        "black" => Ok(Color::Black),
+        "red" => Ok(Color::Red),
+        "green" => Ok(Color::Green),
+        "yellow" => Ok(Color::Yellow),
+        "blue" => Ok(Color::Blue),
+        "magenta" => Ok(Color::Magenta),
+        "cyan" => Ok(Color::Cyan),
+        "white" => Ok(Color::White),
+        "gray" | "grey" => Ok(Color::Gray),
+        "darkgray" | "darkgrey" => Ok(Color::DarkGray),
+        _ => anyhow::bail!("Unrecognized color '{}': expected #rrggbb, 0xrrggbb, or a named color", raw),
+    }
+}
+
+/// User-supplied `[themes.<name>]` tables, loaded from `~/.pecan/themes.toml`.
+#[derive(Deserialize, Default)]
+struct UserThemesFile {
+    #[serde(default)]
+    themes: HashMap<String, Theme>,
+}
+
+/// Merges the built-in palettes with any user-defined ones from
+/// `~/.pecan/themes.toml`, resolving a theme by name at startup. A
+/// user-defined theme with the same name as a built-in overrides it.
+pub struct ThemeRegistry {
+    themes: HashMap<String, Theme>,
+    /// Lazily-computed result of `Theme::auto()`. Left unset until something
+    /// actually resolves `"auto"`, so a user who never configures it never
+    /// pays for the OSC 11 query (or risks the terminal round trip) at all;
+    /// cached afterward so cycling through the picker can't trigger it twice.
+    auto: std::sync::OnceLock<Theme>,
+}
+
+impl ThemeRegistry {
+    pub fn load() -> Self {
+        let mut themes = HashMap::new();
+        themes.insert("dracula".to_string(), DRACULA);
+        themes.insert("nord".to_string(), NORD);
+        themes.insert("default".to_string(), DEFAULT);
+        themes.insert("light".to_string(), LIGHT);
+
+        if let Ok(path) = Self::themes_path() {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                match toml::from_str::<UserThemesFile>(&content) {
+                    Ok(file) => themes.extend(file.themes),
+                    Err(e) => tracing::warn!("Failed to parse {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        Self { themes, auto: std::sync::OnceLock::new() }
+    }
+
+    /// Resolves a theme by name. `"auto"` falls back to `Theme::auto()` (and
+    /// its OSC 11 terminal query) only when there's no user-defined `"auto"`
+    /// theme in `themes.toml` overriding it, and only the first time it's
+    /// actually requested — the result is cached, so resolving any other
+    /// theme never touches the terminal or spawns the query's background
+    /// reader thread.
+    pub fn resolve(&self, name: &str) -> Option<Theme> {
+        let key = name.to_lowercase();
+        if let Some(theme) = self.themes.get(&key) {
+            return Some(*theme);
+        }
+        if key == "auto" {
+            return Some(*self.auto.get_or_init(Theme::auto));
+        }
+        None
+    }
+
+    /// Sorted theme names, for listing in completions and the theme picker.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.themes.keys().cloned().collect();
+        if !names.iter().any(|n| n == "auto") {
+            names.push("auto".to_string());
+        }
+        names.sort();
+        names
+    }
+
+    fn themes_path() -> anyhow::Result<PathBuf> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+        Ok(home.join(".pecan").join("themes.toml"))
+    }
+}
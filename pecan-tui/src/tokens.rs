@@ -0,0 +1,75 @@
+//! Status-bar token accounting. We keep a running total of the tokens already
+//! committed to the transcript and recompute the draft's count only when the
+//! input text actually changes, so the 50ms draw tick never re-tokenizes the
+//! whole conversation.
+
+use tiktoken_rs::{cl100k_base, CoreBPE};
+
+/// Tracks context usage for the status-bar budget meter.
+pub struct TokenMeter {
+    bpe: CoreBPE,
+    /// Tokens committed to the transcript so far.
+    history_tokens: usize,
+    /// Number of `messages` entries already folded into `history_tokens`.
+    counted_messages: usize,
+    /// Last draft seen, and its token count, so unchanged input is free.
+    draft: String,
+    draft_tokens: usize,
+}
+
+impl TokenMeter {
+    pub fn new() -> Self {
+        Self {
+            bpe: cl100k_base().expect("cl100k_base tokenizer ships with tiktoken-rs"),
+            history_tokens: 0,
+            counted_messages: 0,
+            draft: String::new(),
+            draft_tokens: 0,
+        }
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    /// Folds any transcript entries appended since the last call into the
+    /// running total. A shrinking transcript (e.g. after `/clear`) is recounted
+    /// from scratch.
+    pub fn sync_history(&mut self, messages: &[(String, String)]) {
+        if messages.len() < self.counted_messages {
+            self.history_tokens = 0;
+            self.counted_messages = 0;
+        }
+        for (_, content) in &messages[self.counted_messages..] {
+            self.history_tokens += self.count(content);
+        }
+        self.counted_messages = messages.len();
+    }
+
+    /// Refreshes the draft token count, but only when the text changed.
+    pub fn sync_draft(&mut self, draft: &str) {
+        if draft != self.draft {
+            self.draft_tokens = self.count(draft);
+            self.draft.clear();
+            self.draft.push_str(draft);
+        }
+    }
+
+    /// Current history plus draft tokens.
+    pub fn total(&self) -> usize {
+        self.history_tokens + self.draft_tokens
+    }
+}
+
+/// Renders a count as a compact `3.2k`-style string, dropping a trailing `.0`.
+pub fn format_tokens(n: usize) -> String {
+    if n < 1000 {
+        return n.to_string();
+    }
+    let k = n as f64 / 1000.0;
+    if (k - k.round()).abs() < 0.05 {
+        format!("{}k", k.round() as usize)
+    } else {
+        format!("{:.1}k", k)
+    }
+}
@@ -41,6 +41,7 @@ async fn main() -> anyhow::Result<()> {
             api_key: None,
             model_id: None,
             description: None,
+            context_window: None,
         });
         config.default_model = "cli-override".to_string();
     } else if args.mock {
@@ -52,17 +53,17 @@ async fn main() -> anyhow::Result<()> {
     // If a prompt is provided, run it and exit
     if let Some(prompt) = args.prompt {
         match agent.chat(prompt).await {
-            Ok(response) => {
-                if response == "WAITING_FOR_APPROVAL" {
-                    let pending = agent.pending_tool_call.lock().await;
-                    if let Some(p) = &*pending {
-                        println!("Tool Approval Required: {} with args {}", p.tool_name, p.arguments);
-                    }
-                } else {
-                    println!("{}", response);
+            Ok(pecan_core::AgentStatus::AwaitingApproval { .. }) => {
+                let batch = agent.pending_batch.lock().await;
+                if let Some(p) = batch.front() {
+                    println!("Tool Approval Required: {} with args {}", p.tool_name, p.arguments);
                 }
                 return Ok(());
             }
+            Ok(status) => {
+                println!("{}", status);
+                return Ok(());
+            }
             Err(e) => {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);